@@ -37,6 +37,45 @@
 //! # use unsafe_fn::unsafe_fn;
 //! // Equivalent to `unsafe trait UnsafeMarker {}`
 //! #[unsafe_fn] trait UnsafeMarker {}
+//! // Equivalent to `unsafe impl UnsafeMarker for u32 {}`
+//! #[unsafe_fn(unsafe_impl)] impl UnsafeMarker for u32 {}
+//! ```
+//!
+//! Use `#[unsafe_fn(methods)]` to also apply `#[unsafe_fn]` to all the methods of the trait
+//! that do not have their own `#[unsafe_fn]` attribute
+//! ```rust
+//! # use unsafe_fn::unsafe_fn;
+//! #[unsafe_fn(methods)]
+//! trait RawAccess {
+//!     fn get(&self, index: usize) -> u8;
+//! }
+//! ```
+//!
+//! On an `impl` block, `#[unsafe_fn]` applies to all the methods that are not already
+//! `unsafe`
+//! ```rust
+//! # use unsafe_fn::unsafe_fn;
+//! struct Buffer(Vec<u8>);
+//! #[unsafe_fn]
+//! impl Buffer {
+//!     fn get(&self, i: usize) -> u8 { *unsafe { self.0.get_unchecked(i) } }
+//! }
+//! ```
+//!
+//! To mark all the functions of a module at once, use `#[unsafe_fn]` on the module, or the
+//! `unsafe_mod!` macro
+//! ```rust
+//! # use unsafe_fn::unsafe_fn;
+//! #[unsafe_fn]
+//! mod raw {
+//!     pub fn read(ptr: *const i32) -> i32 { unsafe { *ptr } }
+//! }
+//!
+//! unsafe_fn::unsafe_mod! {
+//!     mod raw2 {
+//!         pub fn read(ptr: *const i32) -> i32 { unsafe { *ptr } }
+//!     }
+//! }
 //! ```
 //!
 //! ## Rationale
@@ -70,27 +109,10 @@
 //!
 //! ## Limitations
 //!
-//! Due to a restriction in the way procedural macro works, there are a small limitation:
-//!
-//!  1. associated functions of a generic type that reference neither `self` nor `Self`
-//! cannot reference any of the generic type.
-//!
-//! ```ignore
-//! # use unsafe_fn::unsafe_fn;
-//! struct X<T>(T);
-//! impl<T> X<T> {
-//!     #[unsafe_fn] // ok: reference self
-//!     fn get(&self) -> &T { &self.0 }
-//!
-//!     // Error! no refernces to 'self' or 'Self', so T cannot be used
-//!     #[unsafe_fn]
-//!     fn identity(x : &T) -> &T { x }
-//! // error[E0401]: can't use generic parameters from outer function
-//! }
-//! ```
+//! Due to a restriction in the way procedural macro works, there are a few limitations:
 //!
-//!  2. Within trait implementation this only work if the trait function was also marked
-//!  with #[unsafe_fn]
+//!  1. Within trait implementation this only work if the trait function was also marked
+//!     with #[unsafe_fn]
 //!
 //! ```ignore
 //! # use unsafe_fn::unsafe_fn;
@@ -104,10 +126,49 @@
 //! // error[E0407]: method `__unsafe_fn_fn2` is not a member of trait `Tr`
 //! }
 //! ```
+//!
+//!  2. A function returning `impl Trait` cannot call itself recursively when its body
+//!     is moved to another function (see 3.), because the public function and the function
+//!     containing the body have distinct opaque types.
+//!
+//! ```ignore
+//! # use unsafe_fn::unsafe_fn;
+//! struct Tree(Vec<u8>);
+//! impl Tree {
+//!     #[unsafe_fn]
+//!     fn expand(&self, depth: u32) -> impl Iterator<Item = u8> + '_ {
+//!         if depth == 0 {
+//!             return self.0.iter().copied();
+//!         }
+//!         unsafe { self.expand(depth - 1) }
+//! // error[E0308]: mismatched types
+//!     }
+//! }
+//! ```
+//!
+//!  3. The attribute of a function alone cannot know whether it is in a generic `impl`, so
+//!     the body of a function referencing neither `self` nor `Self` stays in the `unsafe fn`.
+//!     So does the body of a `#[safe_body]` method of a trait implementation, or of a method
+//!     with `#[target_feature]`. The `unsafe_op_in_unsafe_fn` lint then requires the unsafe
+//!     operations to be in `unsafe` blocks, but the compiler does not report it for the code
+//!     of a macro from another crate. To move the body to another function, use
+//!     `#[unsafe_fn(sibling)]` on a free function, `#[unsafe_fn(assoc)]` on an associated
+//!     function, or apply the attribute to the whole module or inherent `impl` block.
+//!
+//! ```ignore
+//! # use unsafe_fn::unsafe_fn;
+//! #[unsafe_fn]
+//! fn read(p: *const u32) -> u32 {
+//!     other_crate::deref!(p) // Not reported when the macro dereferences the pointer
+//! }
+//! ```
 
 extern crate proc_macro;
 use proc_macro::TokenStream;
+use proc_macro2::{Delimiter, TokenStream as TokenStream2, TokenTree};
 use quote::{format_ident, quote};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use syn::{fold::Fold, punctuated::Punctuated, spanned::Spanned, visit::Visit, *};
 
 struct RemoveMut;
@@ -117,11 +178,156 @@ impl Fold for RemoveMut {
         i
     }
     fn fold_receiver(&mut self, mut i: Receiver) -> Receiver {
-        i.mutability = None;
+        // Only `mut self` by value, not `&mut self`
+        if i.reference.is_none() {
+            i.mutability = None;
+        }
         i
     }
 }
 
+/// Find the `unsafe { ... }` blocks within the tokens of a macro invocation, which are
+/// not parsed. Returns the span of the `unsafe` keyword and the content of the block.
+fn unsafe_blocks_in_tokens(
+    tokens: TokenStream2,
+    found: &mut Vec<(proc_macro2::Span, TokenStream2)>,
+) {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(tt) = tokens.next() {
+        match (&tt, tokens.peek()) {
+            (TokenTree::Ident(i), Some(TokenTree::Group(g)))
+                if i == "unsafe" && g.delimiter() == Delimiter::Brace =>
+            {
+                found.push((i.span(), g.stream()));
+            }
+            _ => {}
+        }
+        if let TokenTree::Group(g) = tt {
+            unsafe_blocks_in_tokens(g.stream(), found);
+        }
+    }
+}
+
+/// Statement macros are parsed as items, which the visitors do not recurse in
+fn is_statement_macro(i: &Item) -> Option<&Macro> {
+    match i {
+        Item::Macro(m) if m.ident.is_none() => Some(&m.mac),
+        _ => None,
+    }
+}
+
+struct HasUnsafeBlock(bool);
+impl<'ast> Visit<'ast> for HasUnsafeBlock {
+    fn visit_expr_unsafe(&mut self, _: &'ast ExprUnsafe) {
+        self.0 = true;
+    }
+
+    fn visit_macro(&mut self, m: &'ast Macro) {
+        let mut found = Vec::new();
+        unsafe_blocks_in_tokens(m.tokens.clone(), &mut found);
+        self.0 |= !found.is_empty();
+    }
+
+    fn visit_item(&mut self, i: &'ast Item) {
+        // Do not recurse in other items
+        if let Some(m) = is_statement_macro(i) {
+            self.visit_macro(m);
+        }
+    }
+}
+
+struct HasImplTrait(bool);
+impl<'ast> Visit<'ast> for HasImplTrait {
+    fn visit_type_impl_trait(&mut self, _: &'ast TypeImplTrait) {
+        self.0 = true;
+    }
+}
+
+/// The generic arguments to call a function with the given generic parameters.
+/// Lifetimes cannot always be specified explicitly, so only forward the type and
+/// const parameters, in the order of their declaration. The anonymous parameters of
+/// `impl Trait` arguments are inferred: since Rust 1.63, they do not prevent the other
+/// parameters from being explicit.
+fn turbofish(generics: &Generics) -> TokenStream2 {
+    let generic_args: Vec<_> = generics
+        .params
+        .iter()
+        .filter_map(|p| match p {
+            GenericParam::Type(t) => Some(&t.ident),
+            GenericParam::Const(c) => Some(&c.ident),
+            GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+    if generic_args.is_empty() {
+        quote!()
+    } else {
+        quote!(::< #(#generic_args),* >)
+    }
+}
+
+/// Report the `unsafe` blocks that do not start with a `safety!(...)` justification
+struct UnjustifiedUnsafe(Option<Error>);
+impl<'ast> Visit<'ast> for UnjustifiedUnsafe {
+    fn visit_expr_unsafe(&mut self, e: &'ast ExprUnsafe) {
+        let is_safety = |m: &Macro| m.path.segments.last().is_some_and(|s| s.ident == "safety");
+        let justified = match e.block.stmts.first() {
+            Some(Stmt::Item(Item::Macro(m))) => is_safety(&m.mac),
+            Some(Stmt::Semi(Expr::Macro(m), _)) | Some(Stmt::Expr(Expr::Macro(m))) => {
+                is_safety(&m.mac)
+            }
+            _ => false,
+        };
+        if !justified {
+            self.report(e.unsafe_token.span);
+        }
+        visit::visit_expr_unsafe(self, e);
+    }
+
+    fn visit_macro(&mut self, m: &'ast Macro) {
+        let mut found = Vec::new();
+        unsafe_blocks_in_tokens(m.tokens.clone(), &mut found);
+        for (span, content) in found {
+            // The block must start with a path ending with `safety`, followed by `!`
+            let mut last = None;
+            let mut justified = false;
+            for tt in content {
+                match tt {
+                    TokenTree::Ident(i) => last = Some(i),
+                    TokenTree::Punct(p) if p.as_char() == ':' => {}
+                    TokenTree::Punct(p) if p.as_char() == '!' => {
+                        justified = last.is_some_and(|i| i == "safety");
+                        break;
+                    }
+                    _ => break,
+                }
+            }
+            if !justified {
+                self.report(span);
+            }
+        }
+    }
+
+    fn visit_item(&mut self, i: &'ast Item) {
+        // Do not recurse in other items
+        if let Some(m) = is_statement_macro(i) {
+            self.visit_macro(m);
+        }
+    }
+}
+
+impl UnjustifiedUnsafe {
+    fn report(&mut self, span: proc_macro2::Span) {
+        let err = Error::new(
+            span,
+            "unsafe block must start with a `safety!(\"...\")` justification",
+        );
+        match &mut self.0 {
+            Some(e) => e.combine(err),
+            None => self.0 = Some(err),
+        }
+    }
+}
+
 struct HasSelfType(bool);
 impl<'ast> Visit<'ast> for HasSelfType {
     fn visit_ident(&mut self, i: &'ast Ident) {
@@ -135,6 +341,165 @@ impl<'ast> Visit<'ast> for HasSelfType {
     }
 }
 
+/// Attributes that affect the code of the function. They are moved to the function
+/// that contains the body.
+const BODY_ATTRIBUTES: &[&str] = &["inline", "cold"];
+
+/// Lint attributes apply to both the public function and the function that contains
+/// the body.
+const LINT_ATTRIBUTES: &[&str] = &["allow", "warn", "deny", "forbid"];
+
+/// Attributes that change how the code is compiled and called. They also apply to both:
+/// `target_feature` lets the body of a `sibling` helper use the features, and the public
+/// function can only be called where they are available. (Otherwise the body stays in
+/// place.) `track_caller` needs to be on every function between
+/// the caller and the body. `link_section` and `instruction_set` must apply to the code,
+/// but the public function is also called, e.g. from RAM while the flash is not available.
+/// `cfg` removes all the generated functions, which could use items that are removed too.
+const BOTH_ATTRIBUTES: &[&str] = &[
+    "cfg",
+    "target_feature",
+    "track_caller",
+    "link_section",
+    "instruction_set",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum AttrTarget {
+    Outer,
+    Body,
+    Both,
+    /// The lints are usually triggered by the body, so the expectation is on the function
+    /// containing the body, while they are only allowed on the public function
+    Expect,
+}
+
+fn attribute_target(path: &Path) -> AttrTarget {
+    if BODY_ATTRIBUTES.iter().any(|a| path.is_ident(a)) {
+        AttrTarget::Body
+    } else if path.is_ident("expect") {
+        AttrTarget::Expect
+    } else if LINT_ATTRIBUTES
+        .iter()
+        .chain(BOTH_ATTRIBUTES)
+        .any(|a| path.is_ident(a))
+    {
+        AttrTarget::Both
+    } else {
+        AttrTarget::Outer
+    }
+}
+
+/// An attribute within `#[unsafe(...)]` goes where the attribute it contains goes
+fn meta_target(meta: &Meta) -> AttrTarget {
+    match meta {
+        Meta::List(l) if l.path.is_ident("__unsafe_fn_unsafe_attr") => match l.nested.first() {
+            Some(NestedMeta::Meta(m)) => meta_target(m),
+            _ => AttrTarget::Outer,
+        },
+        m => attribute_target(m.path()),
+    }
+}
+
+/// Parse the content of a `#[cfg_attr(predicate, attr1, attr2, ...)]`
+fn parse_cfg_attr(input: parse::ParseStream) -> Result<(NestedMeta, Vec<Meta>)> {
+    let predicate = input.parse()?;
+    input.parse::<Token![,]>()?;
+    let attrs = Punctuated::<Meta, Token![,]>::parse_terminated(input)?;
+    Ok((predicate, attrs.into_iter().collect()))
+}
+
+/// Turn a `#[expect(...)]`, possibly within a `#[cfg_attr]`, into `#[allow(...)]`
+fn expect_to_allow(attr: &Attribute) -> Attribute {
+    let allow = |path: &Path| Path::from(Ident::new("allow", path.span()));
+    match attr.parse_args_with(parse_cfg_attr) {
+        Ok((predicate, metas)) if attr.path.is_ident("cfg_attr") => {
+            let metas = metas.into_iter().map(|m| match m {
+                Meta::List(mut l) => {
+                    l.path = allow(&l.path);
+                    Meta::List(l)
+                }
+                m => m,
+            });
+            Attribute {
+                tokens: quote!((#predicate, #(#metas),*)),
+                ..attr.clone()
+            }
+        }
+        _ => Attribute {
+            path: allow(&attr.path),
+            ..attr.clone()
+        },
+    }
+}
+
+/// Split the attributes of the function between the ones for the public function
+/// and the ones for the function containing the body.
+/// A `#[cfg_attr]` is split according to the attributes it contains.
+fn split_attributes(attrs: Vec<Attribute>) -> (Vec<Attribute>, Vec<Attribute>) {
+    let mut outer = Vec::new();
+    let mut body = Vec::new();
+    let mut push = |target, attr: Attribute| match target {
+        AttrTarget::Outer => outer.push(attr),
+        AttrTarget::Body => body.push(attr),
+        AttrTarget::Both => {
+            body.push(attr.clone());
+            outer.push(attr);
+        }
+        AttrTarget::Expect => {
+            outer.push(expect_to_allow(&attr));
+            body.push(attr);
+        }
+    };
+    for attr in attrs {
+        if attr.path.is_ident("cfg_attr") {
+            if let Ok((predicate, metas)) = attr.parse_args_with(parse_cfg_attr) {
+                for target in [
+                    AttrTarget::Outer,
+                    AttrTarget::Body,
+                    AttrTarget::Both,
+                    AttrTarget::Expect,
+                ] {
+                    let metas: Vec<_> = metas.iter().filter(|m| meta_target(m) == target).collect();
+                    if !metas.is_empty() {
+                        let tokens = quote!((#predicate, #(#metas),*));
+                        push(
+                            target,
+                            Attribute {
+                                tokens,
+                                ..attr.clone()
+                            },
+                        );
+                    }
+                }
+                continue;
+            }
+        }
+        let target = match attr.parse_meta() {
+            Ok(meta) => meta_target(&meta),
+            Err(_) => attribute_target(&attr.path),
+        };
+        push(target, attr);
+    }
+    (outer, body)
+}
+
+/// Whether one of the attribute is `#[name]`, possibly within a `#[cfg_attr]`
+fn has_attribute(attrs: &[Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path.is_ident(name)
+            || (attr.path.is_ident("__unsafe_fn_unsafe_attr")
+                && attr
+                    .parse_args::<Meta>()
+                    .is_ok_and(|m| m.path().is_ident(name)))
+            || (attr.path.is_ident("cfg_attr")
+                && attr
+                    .parse_args_with(parse_cfg_attr)
+                    .is_ok_and(|(_, metas)| metas.iter().any(|m| m.path().is_ident(name))))
+    })
+}
+
+#[derive(Clone, Copy)]
 enum Kind {
     UnsafeFn,
     SafeBody,
@@ -143,6 +508,7 @@ enum Kind {
 struct FnOrMethod {
     attrs: Vec<Attribute>,
     vis: Visibility,
+    defaultness: Option<Token![default]>,
     sig: Signature,
     block: Option<Block>,
     semi_token: Option<token::Semi>,
@@ -153,6 +519,7 @@ impl From<ItemFn> for FnOrMethod {
         FnOrMethod {
             attrs: itemfn.attrs,
             vis: itemfn.vis,
+            defaultness: None,
             sig: itemfn.sig,
             block: Some(*itemfn.block),
             semi_token: None,
@@ -160,11 +527,25 @@ impl From<ItemFn> for FnOrMethod {
     }
 }
 
+impl From<ImplItemMethod> for FnOrMethod {
+    fn from(m: ImplItemMethod) -> FnOrMethod {
+        FnOrMethod {
+            attrs: m.attrs,
+            vis: m.vis,
+            defaultness: m.defaultness,
+            sig: m.sig,
+            block: Some(m.block),
+            semi_token: None,
+        }
+    }
+}
+
 impl From<TraitItemMethod> for FnOrMethod {
     fn from(m: TraitItemMethod) -> FnOrMethod {
         FnOrMethod {
             attrs: m.attrs,
             vis: Visibility::Inherited,
+            defaultness: None,
             sig: m.sig,
             block: m.default,
             semi_token: m.semi_token,
@@ -172,26 +553,528 @@ impl From<TraitItemMethod> for FnOrMethod {
     }
 }
 
+/// Find the `...` of a C-variadic function in the parentheses of the signature. This is done
+/// on the tokens because the `args: ...` syntax cannot be parsed.
+fn find_c_variadic(item: TokenStream2) -> Option<proc_macro2::Span> {
+    item.into_iter().find_map(|tt| match tt {
+        TokenTree::Group(g) if g.delimiter() == Delimiter::Parenthesis => {
+            let tokens: Vec<_> = g.stream().into_iter().collect();
+            tokens.windows(3).find_map(|w| match w {
+                [TokenTree::Punct(a), TokenTree::Punct(b), TokenTree::Punct(c)]
+                    if a.as_char() == '.' && b.as_char() == '.' && c.as_char() == '.' =>
+                {
+                    Some(a.span())
+                }
+                _ => None,
+            })
+        }
+        _ => None,
+    })
+}
+
+/// The arguments of a C-variadic function cannot be forwarded to the function containing
+/// the body
+fn c_variadic_error(span: proc_macro2::Span) -> TokenStream2 {
+    Error::new(
+        span,
+        "C-variadic functions are not supported by #[unsafe_fn] and #[safe_body]",
+    )
+    .to_compile_error()
+}
+
+/// syn cannot represent the `~const` and `[const]` bounds of const traits, nor the
+/// `#[unsafe(...)]` attributes, so they are replaced by markers before parsing, and restored
+/// by [`restore_unparsable`]
+fn hide_unparsable(item: TokenStream2) -> TokenStream2 {
+    use proc_macro2::{Group, Ident, Punct, Spacing};
+    let mut result = Vec::new();
+    let mut iter = item.into_iter().peekable();
+    while let Some(tt) = iter.next() {
+        // Whether a bracket group is the content of an attribute, `#[...]` or `#![...]`
+        let is_attr = match result.as_slice() {
+            [.., TokenTree::Punct(h), TokenTree::Punct(b)]
+                if h.as_char() == '#' && b.as_char() == '!' =>
+            {
+                true
+            }
+            [.., TokenTree::Punct(h)] => h.as_char() == '#',
+            _ => false,
+        };
+        let marker = match &tt {
+            TokenTree::Punct(p) if p.as_char() == '~' => match iter.peek() {
+                Some(TokenTree::Ident(c)) if c == "const" => {
+                    let span = c.span();
+                    iter.next();
+                    Some(Ident::new("__unsafe_fn_tilde_const", span))
+                }
+                _ => None,
+            },
+            TokenTree::Group(g) if g.delimiter() == Delimiter::Bracket => {
+                let inner: Vec<_> = g.stream().into_iter().collect();
+                match inner.as_slice() {
+                    [TokenTree::Ident(c)] if c == "const" && !is_attr => {
+                        Some(Ident::new("__unsafe_fn_bracket_const", g.span()))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        match (marker, tt) {
+            (Some(marker), _) => {
+                result.push(TokenTree::Ident(marker));
+                result.push(TokenTree::Punct(Punct::new('+', Spacing::Alone)));
+            }
+            (None, TokenTree::Group(g)) => {
+                let mut stream: Vec<_> = g.stream().into_iter().collect();
+                if let [TokenTree::Ident(u), TokenTree::Group(_)] = stream.as_mut_slice() {
+                    if is_attr && g.delimiter() == Delimiter::Bracket && u == "unsafe" {
+                        *u = Ident::new("__unsafe_fn_unsafe_attr", u.span());
+                    }
+                }
+                let stream = hide_unparsable(stream.into_iter().collect());
+                let mut group = Group::new(g.delimiter(), stream);
+                group.set_span(g.span());
+                result.push(TokenTree::Group(group));
+            }
+            (None, tt) => result.push(tt),
+        }
+    }
+    result.into_iter().collect()
+}
+
+/// Replace the markers added by [`hide_unparsable`] by the original syntax
+fn restore_unparsable(item: TokenStream2) -> TokenStream2 {
+    use proc_macro2::{Group, Ident, Punct, Spacing};
+    let mut result = Vec::new();
+    let mut iter = item.into_iter().peekable();
+    while let Some(tt) = iter.next() {
+        match tt {
+            TokenTree::Ident(i) if i == "__unsafe_fn_unsafe_attr" => {
+                result.push(TokenTree::Ident(Ident::new("unsafe", i.span())))
+            }
+            TokenTree::Ident(i)
+                if i == "__unsafe_fn_tilde_const" || i == "__unsafe_fn_bracket_const" =>
+            {
+                if matches!(iter.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '+') {
+                    iter.next();
+                }
+                let c = TokenTree::Ident(Ident::new("const", i.span()));
+                if i == "__unsafe_fn_tilde_const" {
+                    let mut tilde = Punct::new('~', Spacing::Alone);
+                    tilde.set_span(i.span());
+                    result.push(TokenTree::Punct(tilde));
+                    result.push(c);
+                } else {
+                    let mut group = Group::new(Delimiter::Bracket, c.into());
+                    group.set_span(i.span());
+                    result.push(TokenTree::Group(group));
+                }
+            }
+            TokenTree::Group(g) => {
+                let mut group = Group::new(g.delimiter(), restore_unparsable(g.stream()));
+                group.set_span(g.span());
+                result.push(TokenTree::Group(group));
+            }
+            tt => result.push(tt),
+        }
+    }
+    result.into_iter().collect()
+}
+
+/// Where the function is, when the attribute is applied to the enclosing module or impl
+/// block. The attribute of a function alone cannot know whether it is in an `impl`.
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Context {
+    #[default]
+    Unknown,
+    /// A free function of a module, which can have a helper next to it
+    Module,
+    /// An associated function of an inherent impl, which can have an associated helper
+    InherentImpl,
+}
+
+/// Options given as argument of the attribute, e.g. `#[unsafe_fn(methods)]`
+#[derive(Default, Clone)]
+struct Options {
+    /// Also apply `#[unsafe_fn]` to all the methods of a trait
+    methods: Option<Ident>,
+    /// Like `methods`, but also apply `#[safe_body]` to the provided methods that are
+    /// already unsafe
+    all_methods: Option<Ident>,
+    /// Error if the body does not contain any `unsafe` block
+    require_unsafe: Option<Ident>,
+    /// Put the helper of a free function next to it instead of inside it
+    sibling: Option<Ident>,
+    /// The function is in an `impl` block, so the helper can be an associated function
+    assoc: Option<Ident>,
+    /// Require all `unsafe` blocks to start with `safety!(...)`
+    safety_comment: Option<Ident>,
+    /// Generate a safe `safe_<name>` function for the tests
+    debug_safe_test: Option<Ident>,
+    /// Keep the body in the public function, without helper
+    in_place: Option<Ident>,
+    /// Make a trait implementation an `unsafe impl`
+    unsafe_impl: Option<Ident>,
+    /// Extra attributes for the public function
+    outer: Vec<Attribute>,
+    /// Extra attributes for the function containing the body
+    inner: Vec<Attribute>,
+    /// Not an option: set when the attribute is applied to a module or impl block
+    context: Context,
+}
+
+/// Parse the content of `outer(...)` or `inner(...)`, a list of attributes without `#[]`
+fn parse_attribute_list(tokens: TokenStream2) -> Result<Vec<Attribute>> {
+    let mut result = Vec::new();
+    let mut current = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(tt) = tokens.next() {
+        let separator = matches!(&tt, TokenTree::Punct(p) if p.as_char() == ',');
+        if !separator {
+            current.push(tt);
+        }
+        if separator || tokens.peek().is_none() {
+            // `unsafe(...)` attributes, such as `unsafe(no_mangle)`, are hidden like the
+            // ones of the item, and restored in the output
+            let attr = std::mem::take(&mut current);
+            result.extend(parse::Parser::parse2(
+                Attribute::parse_outer,
+                hide_unparsable(quote!(#[#(#attr)*])),
+            )?);
+        }
+    }
+    Ok(result)
+}
+
+impl parse::Parse for Options {
+    fn parse(input: parse::ParseStream) -> Result<Self> {
+        let mut options = Options::default();
+        while !input.is_empty() {
+            // The attributes within `outer(...)` and `inner(...)` are not always a `Meta`
+            if input.peek(Ident) && input.peek2(token::Paren) {
+                let fork = input.fork();
+                let name: Ident = fork.parse()?;
+                if name == "outer" || name == "inner" {
+                    input.parse::<Ident>()?;
+                    let content;
+                    parenthesized!(content in input);
+                    let attrs = parse_attribute_list(content.parse()?)?;
+                    if name == "outer" {
+                        options.outer.extend(attrs);
+                    } else {
+                        options.inner.extend(attrs);
+                    }
+                    if input.is_empty() {
+                        break;
+                    }
+                    input.parse::<Token![,]>()?;
+                    continue;
+                }
+            }
+            let meta: Meta = input.parse()?;
+            match &meta {
+                Meta::Path(p) if p.is_ident("methods") => options.methods = p.get_ident().cloned(),
+                Meta::Path(p) if p.is_ident("all_methods") => {
+                    options.all_methods = p.get_ident().cloned()
+                }
+                Meta::Path(p) if p.is_ident("require_unsafe") => {
+                    options.require_unsafe = p.get_ident().cloned()
+                }
+                Meta::Path(p) if p.is_ident("sibling") => options.sibling = p.get_ident().cloned(),
+                Meta::Path(p) if p.is_ident("assoc") => options.assoc = p.get_ident().cloned(),
+                Meta::Path(p) if p.is_ident("safety_comment") => {
+                    options.safety_comment = p.get_ident().cloned()
+                }
+                Meta::Path(p) if p.is_ident("debug_safe_test") => {
+                    options.debug_safe_test = p.get_ident().cloned()
+                }
+                Meta::Path(p) if p.is_ident("in_place") => {
+                    options.in_place = p.get_ident().cloned()
+                }
+                Meta::Path(p) if p.is_ident("unsafe_impl") => {
+                    options.unsafe_impl = p.get_ident().cloned()
+                }
+                _ => return Err(Error::new(meta.span(), "unknown #[unsafe_fn] option")),
+            }
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        Ok(options)
+    }
+}
+
 /// Mark a function as unsafe without its body being in an unsafe block
 ///
 /// See [crate documentation](index.html)
+///
+/// The attribute accepts these options:
+///  - `methods`: on a trait, also apply `#[unsafe_fn]` to all the methods of the trait
+///    that do not have their own `#[unsafe_fn]` attribute.
+///  - `all_methods`: like `methods`, but also apply [`#[safe_body]`](safe_body) to the
+///    provided methods of the trait that are already `unsafe`.
+///  - `require_unsafe`: produce an error if the body does not contain any `unsafe` block,
+///    including within the arguments of macro invocations.
+///  - `sibling`: for a free function, generate the function containing the body next to
+///    the function instead of inside it. Only valid for functions at module level or
+///    local to a block, that are not within an `impl` block.
+///  - `assoc`: for an associated function within an `impl` block, generate the function
+///    containing the body as an associated function. This is done automatically when the
+///    function references `self` or `Self`.
+///  - `safety_comment`: produce an error for every `unsafe` block in the body that does
+///    not start with a [`safety!`](safety!) justification, including the blocks within
+///    the arguments of macro invocations, such as `vec![unsafe { ... }]`.
+///  - `debug_safe_test`: also generate, only with `cfg(test)`, a safe `safe_<name>` function
+///    that calls the function within an `unsafe` block, so that tests do not need `unsafe`
+///    for functions that are called with inputs known to be valid. It keeps the
+///    `#[must_use]` and `#[deprecated]` of the function. It cannot be used for the methods
+///    of a trait implementation.
+///  - `in_place`: keep the body in the public function instead of generating a function
+///    containing it, also for the methods. The `unsafe` operations in the body are then
+///    required to be in `unsafe` blocks by the `unsafe_op_in_unsafe_fn` lint. This is
+///    useful for a function exported with `#[no_mangle]` or `#[export_name]`, so that the
+///    exported symbol contains the code without any indirection.
+///  - `outer(...)` and `inner(...)`: attributes, written without `#[]` and separated by
+///    commas, to add to the public function or to the function containing the body,
+///    e.g. `#[unsafe_fn(outer(track_caller), inner(track_caller, inline(never)))]`. When
+///    the body stays in the public function, both are added to it.
+///  - `unsafe_impl`: on the implementation of an unsafe trait, such as a marker trait,
+///    make it an `unsafe impl`. Its items are kept as they are.
+///
+/// The attribute can be applied to local functions declared within the body of another
+/// function, including the ones produced by `macro_rules!`. It can also be applied
+/// conditionally, e.g. with `#[cfg_attr(feature = "strict", unsafe_fn)]`, also on the items
+/// of an `impl` block or a module with `#[unsafe_fn]`. The ABI of an `extern`
+/// function, such as `"system"` or `"C-unwind"`, is kept on the public function, while the
+/// function containing the body uses the Rust ABI. A naked function is only marked `unsafe`,
+/// since its assembly block cannot be moved to another function. So is a function with
+/// `#[target_feature]` that does not use `sibling`, since the methods of a trait
+/// implementation cannot have target features when they are safe.
+///
+/// On an `impl` block, the attribute is applied with the same options to all the methods
+/// that are not already `unsafe` and that do not have their own `#[unsafe_fn]` attribute.
+/// On a module, it is applied like [`unsafe_mod!`](unsafe_mod!) does, with the same
+/// options. On a type alias of a function pointer type, it makes the function pointer
+/// `unsafe`. On an `extern` block, it makes it an `unsafe extern` block, in which the
+/// items can be declared `safe` or `unsafe`.
+///
+/// For a method declared without body in a trait, the attribute marks the method as
+/// `unsafe` and adds a safe provided method named `__unsafe_fn_` followed by the name of
+/// the method (without the `r#` prefix of a raw identifier). It has the same generic
+/// parameters and arguments as the method, with an extra `Self: Sized` bound. If the
+/// method returns `impl Trait`, the default body of the placeholder calls the method.
+/// Implementations using `#[unsafe_fn]` override it with the body, and implement the
+/// method by calling it. Code generated by other macros can follow the same rule to
+/// implement such trait.
 #[proc_macro_attribute]
-pub fn unsafe_fn(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    if let Ok(m) = parse::<TraitItemMethod>(item.clone()) {
-        return unsafe_fn_impl(m.into(), Kind::UnsafeFn);
+pub fn unsafe_fn(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let options = parse_macro_input!(attr as Options);
+    restore_unparsable(unsafe_fn_attr(options, hide_unparsable(item.into()))).into()
+}
+
+fn unsafe_fn_attr(options: Options, item: TokenStream2) -> TokenStream2 {
+    if let Some(result) = unsafe_extern_impl(item.clone()) {
+        return result;
+    }
+    if let Ok(Item::Type(t)) = parse2::<Item>(item.clone()) {
+        if let Type::BareFn(_) = *t.ty {
+            return unsafe_fn_pointer_impl(t);
+        }
+    }
+    if let Some(dots) = find_c_variadic(item.clone()) {
+        return c_variadic_error(dots);
+    }
+    if let Some(unsafe_impl) = &options.unsafe_impl {
+        return match parse2::<Item>(item) {
+            Ok(Item::Impl(mut i)) if i.trait_.is_some() => match i.unsafety {
+                Some(u) => {
+                    Error::new(u.span(), "#[unsafe_fn] already marked unsafe").to_compile_error()
+                }
+                None => {
+                    i.unsafety = Some(Token![unsafe](i.impl_token.span));
+                    quote!(#i)
+                }
+            },
+            _ => Error::new(
+                unsafe_impl.span(),
+                "`unsafe_impl` can only be used on trait implementations",
+            )
+            .to_compile_error(),
+        };
+    }
+    if let Ok(Item::Trait(t)) = parse2::<Item>(item.clone()) {
+        return unsafe_trait_impl(t, options);
+    }
+    if let Some(methods) = options.methods.as_ref().or(options.all_methods.as_ref()) {
+        return Error::new(
+            methods.span(),
+            format!("`{}` can only be used on traits", methods),
+        )
+        .to_compile_error();
     }
 
-    let item = parse_macro_input!(item as Item);
+    if let Ok(m) = parse2::<TraitItemMethod>(item.clone()) {
+        return unsafe_fn_impl(m.into(), Kind::UnsafeFn, &options);
+    }
+    // Methods with the `default` qualifier of specialization
+    if let Ok(ImplItem::Method(m)) = parse2::<ImplItem>(item.clone()) {
+        if m.defaultness.is_some() {
+            return unsafe_fn_impl(m.into(), Kind::UnsafeFn, &options);
+        }
+    }
+    // Associated constants and types do not parse as an `Item`
+    if let Ok(TraitItem::Const(_) | TraitItem::Type(_)) = parse2::<TraitItem>(item.clone()) {
+        return Error::new(
+            item.span(),
+            "#[unsafe_fn] cannot be applied to constants or types, only to functions, traits, impl blocks, modules, extern blocks or type aliases of function pointers",
+        )
+        .to_compile_error();
+    }
+
+    let item = match parse2::<Item>(item) {
+        Ok(item) => item,
+        Err(e) => return e.to_compile_error(),
+    };
     match item {
-        Item::Fn(f) => unsafe_fn_impl(f.into(), Kind::UnsafeFn),
-        Item::Trait(t) => quote!(unsafe #t).into(),
+        Item::Fn(f) => unsafe_fn_impl(f.into(), Kind::UnsafeFn, &options),
+        Item::Impl(i) => impl_block_impl(i, Kind::UnsafeFn, &options),
+        Item::Mod(m) => unsafe_mod_impl(m, Kind::UnsafeFn, &options),
         _ => Error::new(
             item.span(),
-            "#[unsafe_fn] can only be applied to functions or traits",
+            "#[unsafe_fn] can only be applied to functions, traits, impl blocks, modules, extern blocks or type aliases of function pointers",
         )
-        .to_compile_error()
-        .into(),
+        .to_compile_error(),
+    }
+}
+
+fn unsafe_trait_impl(mut t: ItemTrait, options: Options) -> TokenStream2 {
+    if let Some(u) = t.unsafety {
+        return Error::new(u.span(), "#[unsafe_fn] already marked unsafe").to_compile_error();
+    }
+    let all_methods = options.all_methods.is_some();
+    if options.methods.is_some() || all_methods {
+        for it in t.items.iter_mut() {
+            match it {
+                TraitItem::Method(m)
+                    if m.sig.unsafety.is_none() && !has_own_attribute(&m.attrs) =>
+                {
+                    *it = TraitItem::Verbatim(unsafe_fn_impl(
+                        m.clone().into(),
+                        Kind::UnsafeFn,
+                        &options,
+                    ))
+                }
+                TraitItem::Method(m)
+                    if all_methods && m.default.is_some() && !has_own_attribute(&m.attrs) =>
+                {
+                    *it = TraitItem::Verbatim(unsafe_fn_impl(
+                        m.clone().into(),
+                        Kind::SafeBody,
+                        &options,
+                    ))
+                }
+                _ => {}
+            }
+        }
+    }
+    // The attributes come before the `unsafe` keyword
+    t.unsafety = Some(Token![unsafe](t.trait_token.span));
+    quote!(#t)
+}
+
+/// Items with their own `#[unsafe_fn]` or `#[safe_body]` attribute are left to it when
+/// the attribute is applied to a whole impl block or module. The `#[cfg_attr]` of the
+/// items are not expanded yet, so the attribute may also be within one.
+fn has_own_attribute(attrs: &[Attribute]) -> bool {
+    let is_own = |path: &Path| {
+        path.segments
+            .last()
+            .is_some_and(|s| s.ident == "unsafe_fn" || s.ident == "safe_body")
+    };
+    attrs.iter().any(|a| {
+        is_own(&a.path)
+            || (a.path.is_ident("cfg_attr")
+                && a.parse_args_with(parse_cfg_attr)
+                    .is_ok_and(|(_, metas)| metas.iter().any(|m| is_own(m.path()))))
+    })
+}
+
+/// Whether `#[unsafe_fn]` or `#[safe_body]` applied to a whole impl block or module should
+/// be applied to this function. With `#[unsafe_fn]`, the functions that are already `unsafe`
+/// are left untouched, and with `#[safe_body]`, only these are changed.
+fn applies_to(k: Kind, unsafety: Option<Token![unsafe]>, attrs: &[Attribute]) -> bool {
+    unsafety.is_some() == matches!(k, Kind::SafeBody) && !has_own_attribute(attrs)
+}
+
+/// Mark an `extern` block as `unsafe`. This is done on the tokens, so that the `safe` and
+/// `unsafe` qualifiers of the items, which cannot be parsed, are kept as is.
+/// Returns `None` if the item is not an `extern` block.
+fn unsafe_extern_impl(item: TokenStream2) -> Option<TokenStream2> {
+    let tokens: Vec<_> = item.into_iter().collect();
+    // Skip the outer attributes
+    let mut i = 0;
+    while let (Some(TokenTree::Punct(p)), Some(TokenTree::Group(g))) =
+        (tokens.get(i), tokens.get(i + 1))
+    {
+        if p.as_char() != '#' || g.delimiter() != Delimiter::Bracket {
+            break;
+        }
+        i += 2;
     }
+    let (unsafety, rest) = match &tokens[i..] {
+        [TokenTree::Ident(u), rest @ ..] if u == "unsafe" => (Some(u), rest),
+        rest => (None, rest),
+    };
+    let extern_token = match rest {
+        [TokenTree::Ident(e), TokenTree::Group(g)]
+        | [TokenTree::Ident(e), TokenTree::Literal(_), TokenTree::Group(g)]
+            if e == "extern" && g.delimiter() == Delimiter::Brace =>
+        {
+            e
+        }
+        _ => return None,
+    };
+    if let Some(u) = unsafety {
+        return Some(Error::new(u.span(), "#[unsafe_fn] already marked unsafe").to_compile_error());
+    }
+    let unsafe_token = Token![unsafe](extern_token.span());
+    let (attrs, rest) = tokens.split_at(i);
+    Some(quote!(#(#attrs)* #unsafe_token #(#rest)*))
+}
+
+/// Mark the function pointer type of a type alias as `unsafe`
+fn unsafe_fn_pointer_impl(mut t: ItemType) -> TokenStream2 {
+    if let Type::BareFn(f) = t.ty.as_mut() {
+        if let Some(u) = f.unsafety {
+            return Error::new(u.span(), "#[unsafe_fn] already marked unsafe").to_compile_error();
+        }
+        f.unsafety = Some(Token![unsafe](f.fn_token.span));
+    }
+    quote!(#t)
+}
+
+/// Apply `#[unsafe_fn]` or `#[safe_body]` to all the methods of an impl block
+fn impl_block_impl(mut i: ItemImpl, k: Kind, options: &Options) -> TokenStream2 {
+    // The methods of a trait implementation cannot have a helper that is not in the trait
+    let options = &Options {
+        context: if i.trait_.is_none() {
+            Context::InherentImpl
+        } else {
+            Context::Unknown
+        },
+        ..options.clone()
+    };
+    for it in i.items.iter_mut() {
+        match it {
+            ImplItem::Method(m) if applies_to(k, m.sig.unsafety, &m.attrs) => {
+                *it = ImplItem::Verbatim(unsafe_fn_impl(m.clone().into(), k, options))
+            }
+            _ => {}
+        }
+    }
+    quote!(#i)
 }
 
 /// Make the body of an unsafe function not allowed to call unsafe code without
@@ -209,25 +1092,264 @@ pub fn unsafe_fn(_attr: TokenStream, item: TokenStream) -> TokenStream {
 ///     a + b // safe code outside of the unsafe block
 /// }
 /// ```
+///
+/// It can be used in the implementation of any trait, even if the trait does not use
+/// `#[unsafe_fn]`.
+///
+/// In a trait, it can be applied to the provided methods.
+///
+/// On an `impl` block, it applies to all the `unsafe` methods that do not have their own
+/// `#[safe_body]` attribute. On a module, it applies to all the `unsafe` functions, including
+/// the methods of the impl blocks and the items of the nested modules.
 #[proc_macro_attribute]
 pub fn safe_body(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    if let Ok(m) = parse::<TraitItemMethod>(item.clone()) {
-        return unsafe_fn_impl(m.into(), Kind::SafeBody);
+    restore_unparsable(safe_body_attr(hide_unparsable(item.into()))).into()
+}
+
+fn safe_body_attr(item: TokenStream2) -> TokenStream2 {
+    if let Some(dots) = find_c_variadic(item.clone()) {
+        return c_variadic_error(dots);
+    }
+    match parse2::<Item>(item.clone()) {
+        Ok(Item::Impl(i)) => return impl_block_impl(i, Kind::SafeBody, &Options::default()),
+        Ok(Item::Mod(m)) => return unsafe_mod_impl(m, Kind::SafeBody, &Options::default()),
+        _ => {}
+    }
+    if let Ok(m) = parse2::<TraitItemMethod>(item.clone()) {
+        return unsafe_fn_impl(m.into(), Kind::SafeBody, &Options::default());
+    }
+    // Methods with the `default` qualifier of specialization
+    if let Ok(ImplItem::Method(m)) = parse2::<ImplItem>(item.clone()) {
+        if m.defaultness.is_some() {
+            return unsafe_fn_impl(m.into(), Kind::SafeBody, &Options::default());
+        }
+    }
+    match parse2::<ItemFn>(item) {
+        Ok(item) => unsafe_fn_impl(item.into(), Kind::SafeBody, &Options::default()),
+        Err(e) => e.to_compile_error(),
     }
-    let item = parse_macro_input!(item as ItemFn);
-    unsafe_fn_impl(item.into(), Kind::SafeBody)
+}
+
+/// Apply `#[safe_body]` to each of the items it contains
+///
+/// This is the function-like form of the attribute, like
+/// [`unsafe_fn_items!`](unsafe_fn_items!).
+///
+/// ```rust
+/// unsafe_fn::safe_body_items! {
+///     unsafe fn read(ptr: *const i32) -> i32 {
+///         unsafe { *ptr }
+///     }
+/// }
+///
+/// assert_eq!(unsafe { read(&42) }, 42);
+/// ```
+#[proc_macro]
+pub fn safe_body_items(input: TokenStream) -> TokenStream {
+    let input = hide_unparsable(input.into()).into();
+    let Items(items) = parse_macro_input!(input as Items);
+    let result = items
+        .into_iter()
+        .map(|item| safe_body_attr(quote!(#item)))
+        .collect::<TokenStream2>();
+    restore_unparsable(result).into()
+}
+
+/// Make a closure that is unsafe to call
+///
+/// The closure is wrapped in a value with an `unsafe fn call(&self, ...)` method, and an
+/// `unsafe fn call_mut(&mut self, ...)` method if it is `FnMut`. Its body is not unsafe.
+/// The arguments of the closure must have a type.
+///
+/// ```rust
+/// let read = unsafe_fn::unsafe_closure!(|p: *const u8| unsafe { *p });
+/// assert_eq!(unsafe { read.call(&42) }, 42);
+/// ```
+///
+/// A procedural macro crate cannot provide types, so the type of the wrapper cannot be
+/// named, like the type of a closure. To store it, a closure that does not capture
+/// anything can instead be converted to an `unsafe fn` pointer.
+#[proc_macro]
+pub fn unsafe_closure(input: TokenStream) -> TokenStream {
+    let closure = parse_macro_input!(input as ExprClosure);
+    let mut names = Vec::new();
+    let mut types = Vec::new();
+    for (i, input) in closure.inputs.iter().enumerate() {
+        match input {
+            Pat::Type(t) => {
+                names.push(format_ident!("__unsafe_fn_arg{}", i));
+                types.push(&t.ty);
+            }
+            _ => {
+                return Error::new(
+                    input.span(),
+                    "the arguments of the closure must have a type",
+                )
+                .to_compile_error()
+                .into()
+            }
+        }
+    }
+    quote!({
+        struct UnsafeClosure<F>(F);
+        impl<F> UnsafeClosure<F> {
+            #[allow(dead_code)]
+            #[inline]
+            unsafe fn call<R>(&self, #(#names: #types),*) -> R
+            where
+                F: Fn(#(#types),*) -> R,
+            {
+                (self.0)(#(#names),*)
+            }
+            #[allow(dead_code)]
+            #[inline]
+            unsafe fn call_mut<R>(&mut self, #(#names: #types),*) -> R
+            where
+                F: FnMut(#(#types),*) -> R,
+            {
+                (self.0)(#(#names),*)
+            }
+        }
+        UnsafeClosure(#closure)
+    })
+    .into()
+}
+
+/// Justify why an `unsafe` block is sound
+///
+/// Procedural macros cannot see the comments, so `#[unsafe_fn(safety_comment)]` cannot
+/// check for `// SAFETY:` comments. Instead, it requires every `unsafe` block to
+/// start with this macro, which expands to nothing.
+///
+/// ```rust
+/// use unsafe_fn::{safety, unsafe_fn};
+///
+/// #[unsafe_fn(safety_comment)]
+/// fn read(ptr: *const i32) -> i32 {
+///     unsafe {
+///         safety!("the caller guarantees that ptr is valid");
+///         *ptr
+///     }
+/// }
+/// ```
+#[proc_macro]
+pub fn safety(item: TokenStream) -> TokenStream {
+    parse_macro_input!(item as LitStr);
+    TokenStream::new()
+}
+
+/// Apply `#[unsafe_fn]` to each of the items it contains
+///
+/// This is the function-like form of the attribute, which composes better with the
+/// code generated by other macros. (It cannot be named `unsafe_fn!` because it would
+/// conflict with the attribute.)
+///
+/// ```rust
+/// unsafe_fn::unsafe_fn_items! {
+///     fn read(ptr: *const i32) -> i32 {
+///         unsafe { *ptr }
+///     }
+///     fn write(ptr: *mut i32, v: i32) {
+///         unsafe { *ptr = v }
+///     }
+/// }
+///
+/// let mut x = 42;
+/// unsafe { write(&mut x, 43) };
+/// assert_eq!(unsafe { read(&x) }, 43);
+/// ```
+#[proc_macro]
+pub fn unsafe_fn_items(input: TokenStream) -> TokenStream {
+    let input = hide_unparsable(input.into()).into();
+    let Items(items) = parse_macro_input!(input as Items);
+    let result = items
+        .into_iter()
+        .map(|item| unsafe_fn_attr(Options::default(), quote!(#item)))
+        .collect::<TokenStream2>();
+    restore_unparsable(result).into()
+}
+
+/// The content of a function-like macro taking a list of items
+struct Items(Vec<Item>);
+impl parse::Parse for Items {
+    fn parse(input: parse::ParseStream) -> Result<Self> {
+        let mut items = Vec::new();
+        while !input.is_empty() {
+            items.push(input.parse()?);
+        }
+        Ok(Items(items))
+    }
+}
+
+/// Apply `#[unsafe_fn]` to every function of a module
+///
+/// Attribute macros cannot be used as inner attribute (`#![unsafe_fn]`), so this
+/// function-like macro takes a whole module and marks all the functions it contains,
+/// including the ones in nested modules, as with `#[unsafe_fn]`.
+/// Functions that are already `unsafe` are left untouched.
+/// This is the same as `#[unsafe_fn]` on the module.
+///
+/// ```rust
+/// unsafe_fn::unsafe_mod! {
+///     mod raw {
+///         pub fn read(ptr: *const i32) -> i32 {
+///             unsafe { *ptr }
+///         }
+///     }
+/// }
+///
+/// let x = 42;
+/// assert_eq!(unsafe { raw::read(&x) }, 42);
+/// ```
+#[proc_macro]
+pub fn unsafe_mod(item: TokenStream) -> TokenStream {
+    let item = hide_unparsable(item.into()).into();
+    let item = parse_macro_input!(item as ItemMod);
+    restore_unparsable(unsafe_mod_impl(item, Kind::UnsafeFn, &Options::default())).into()
+}
+
+/// Apply `#[unsafe_fn]` or `#[safe_body]` to all the functions of a module and of its nested
+/// modules. `#[safe_body]` also applies to the methods of the impl blocks.
+fn unsafe_mod_impl(mut item: ItemMod, k: Kind, options: &Options) -> TokenStream2 {
+    let content = match &mut item.content {
+        Some((_, content)) => content,
+        None => {
+            return Error::new(item.span(), "the module must have a body").to_compile_error();
+        }
+    };
+    let fn_options = &Options {
+        context: Context::Module,
+        ..options.clone()
+    };
+    for it in content.iter_mut() {
+        match it {
+            Item::Fn(f) if applies_to(k, f.sig.unsafety, &f.attrs) => {
+                *it = Item::Verbatim(unsafe_fn_impl(f.clone().into(), k, fn_options))
+            }
+            Item::Impl(i) if matches!(k, Kind::SafeBody) && !has_own_attribute(&i.attrs) => {
+                *it = Item::Verbatim(impl_block_impl(i.clone(), k, options))
+            }
+            Item::Mod(m) if !has_own_attribute(&m.attrs) => {
+                *it = Item::Verbatim(unsafe_mod_impl(m.clone(), k, options))
+            }
+            _ => {}
+        }
+    }
+    quote!(#item)
 }
 
 fn unsafe_fn_impl(
     FnOrMethod {
         attrs,
         vis,
+        defaultness,
         sig,
         block,
         semi_token,
     }: FnOrMethod,
     k: Kind,
-) -> TokenStream {
+    options: &Options,
+) -> TokenStream2 {
     let Signature {
         constness,
         asyncness,
@@ -242,21 +1364,24 @@ fn unsafe_fn_impl(
         output,
     } = &sig;
 
+    if let Some(v) = variadic {
+        return c_variadic_error(v.dots.spans[0]);
+    }
+
+    let safe_body = matches!(k, Kind::SafeBody);
     let unsafety = match (k, unsafety) {
-        (Kind::UnsafeFn, None) => <Token![unsafe]>::default(),
-        (Kind::SafeBody, Some(u)) => u.clone(),
+        // Use the span of the `fn` token so diagnostics about the signature point to it
+        (Kind::UnsafeFn, None) => Token![unsafe](fn_token.span),
+        (Kind::SafeBody, Some(u)) => *u,
         (Kind::UnsafeFn, Some(u)) => {
-            return Error::new(u.span(), "#[unsafe_fn] already marked unsafe")
-                .to_compile_error()
-                .into()
+            return Error::new(u.span(), "#[unsafe_fn] already marked unsafe").to_compile_error()
         }
         (Kind::SafeBody, None) => {
             return Error::new(
-                proc_macro::Span::call_site().into(),
+                proc_macro2::Span::call_site(),
                 "#[safe_body] function must be marked as unsafe",
             )
             .to_compile_error()
-            .into()
         }
     };
 
@@ -265,33 +1390,130 @@ fn unsafe_fn_impl(
     let unsafe_fn_name = format_ident!("__unsafe_fn_{}", ident);
 
     let block = match block {
+        // The implementations of the method do not need a placeholder
+        None if safe_body => return quote!(#(#attrs)* #vis #sig #semi_token),
         None => {
             // Trait method, just mark it as unsafe, but also create a dummy placeholder
             // function next to it so re-implementaiton works
-            let inner_where = match &where_clause {
-                Some(w) => quote!(#w, Self:Sized),
-                None => quote!(where Self:Sized),
+            let mut inner_generics = generics.clone();
+            inner_generics
+                .make_where_clause()
+                .predicates
+                .push(parse_quote!(Self: Sized));
+            let inner_where = &inner_generics.where_clause;
+
+            // The type of a `panic!` does not implement the trait of an `impl Trait`
+            // return type, so such placeholder calls the method instead: its result has
+            // the bounds declared by the trait. Implementations that do not use the
+            // placeholder (e.g. without `self`) then do not need to provide it.
+            let mut has_impl_trait = HasImplTrait(false);
+            has_impl_trait.visit_return_type(output);
+            let mut placeholder_inputs = inputs.clone();
+            let placeholder_body = if has_impl_trait.0 {
+                let mut args = Vec::new();
+                for (i, input) in placeholder_inputs.iter_mut().enumerate() {
+                    match input {
+                        FnArg::Receiver(_) => args.push(quote!(self)),
+                        FnArg::Typed(t) => {
+                            let cfgs = t.attrs.iter().filter(|a| a.path.is_ident("cfg"));
+                            match t.pat.as_ref() {
+                                Pat::Ident(p) if p.ident == "self" => {
+                                    args.push(quote!(#(#cfgs)* self))
+                                }
+                                _ => {
+                                    let name = format_ident!("__unsafe_fn_arg{}", i);
+                                    args.push(quote!(#(#cfgs)* #name));
+                                    t.pat = parse_quote!(#name);
+                                }
+                            }
+                        }
+                    }
+                }
+                let turbo = turbofish(generics);
+                let dot_await = asyncness.map(|_| quote!(.await));
+                quote!({
+                    unsafe { Self::#ident #turbo(#(#args),*) } #dot_await
+                })
+            } else {
+                quote!({
+                    ::core::panic!("Not to be called");
+                })
             };
 
+            let (outer, inner) = (&options.outer, &options.inner);
+            let cfgs = attrs.iter().filter(|a| a.path.is_ident("cfg"));
             return quote!(
-                #(#attrs)* #vis #constness #asyncness #unsafety #abi
+                #(#attrs)* #(#outer)* #vis #constness #asyncness #unsafety #abi
                 #fn_token #ident #impl_generics (#inputs #variadic) #output #where_clause
                 #semi_token
 
-                #[doc(hide)]
+                #(#cfgs)*
+                #[doc(hidden)]
                 #[inline]
+                #[allow(unused_variables)]
+                #(#inner)*
                 #constness #asyncness
-                #fn_token #unsafe_fn_name #impl_generics (#inputs #variadic) #output #inner_where
-                { ::std::panic!("Not to be called"); }
-            )
-            .into();
+                #fn_token #unsafe_fn_name #impl_generics (#placeholder_inputs #variadic) #output #inner_where
+                #placeholder_body
+            );
         }
         Some(block) => block,
     };
 
+    if options.require_unsafe.is_some() {
+        let mut has_unsafe = HasUnsafeBlock(false);
+        has_unsafe.visit_block(&block);
+        if !has_unsafe.0 {
+            return Error::new(
+                ident.span(),
+                "#[unsafe_fn(require_unsafe)] function does not contain any unsafe block",
+            )
+            .to_compile_error();
+        }
+    }
+
+    if options.safety_comment.is_some() {
+        let mut unjustified = UnjustifiedUnsafe(None);
+        unjustified.visit_block(&block);
+        if let Some(err) = unjustified.0 {
+            return err.to_compile_error();
+        }
+    }
+
+    // A naked function consists of a single assembly block, which cannot be moved to a
+    // helper
+    let naked = has_attribute(&attrs, "naked");
+    if naked || options.in_place.is_some() {
+        if let Some(o) = options.sibling.as_ref().or(options.assoc.as_ref()) {
+            let reason = if naked {
+                "on a naked function"
+            } else {
+                "with `in_place`"
+            };
+            return Error::new(o.span(), format!("`{}` cannot be used {}", o, reason))
+                .to_compile_error();
+        }
+    }
+
+    // Without a helper, the function keeps all its attributes
+    let all_attrs = attrs
+        .iter()
+        .chain(&options.outer)
+        .chain(&options.inner)
+        .cloned()
+        .collect::<Vec<_>>();
+    let (mut attrs, mut body_attrs) = split_attributes(attrs);
+    attrs.extend(options.outer.iter().cloned());
+    body_attrs.extend(options.inner.iter().cloned());
+    let inline = if has_attribute(&body_attrs, "inline") {
+        quote!()
+    } else {
+        quote!(#[inline])
+    };
+
     let mut main_param = Punctuated::<FnArg, Token!(,)>::new();
     let mut sub_param = Punctuated::<FnArg, Token!(,)>::new();
-    let mut sub_args = Punctuated::<Ident, Token!(,)>::new();
+    let mut sub_args = Punctuated::<TokenStream2, Token!(,)>::new();
     let mut wrap_self = false;
 
     for it in inputs.iter() {
@@ -307,72 +1529,360 @@ fn unsafe_fn_impl(
                 colon_token,
                 ty,
             }) => {
+                // A parameter removed by `#[cfg]` must also be removed from the call
+                let cfgs = attrs.iter().filter(|a| a.path.is_ident("cfg"));
                 if let Pat::Ident(i) = pat.as_ref() {
                     main_param.push(RemoveMut.fold_fn_arg(it.clone()));
                     sub_param.push(it.clone());
                     if i.ident == "self" {
                         wrap_self = true;
                     } else {
-                        sub_args.push(i.ident.clone());
+                        let ident = &i.ident;
+                        sub_args.push(quote!(#(#cfgs)* #ident));
                     }
                 } else {
                     let name = format_ident!("__unsafe_fn_arg{}", sub_args.len());
                     main_param
-                        .push(parse(quote!(#(#attrs)$* #name #colon_token #ty).into()).unwrap());
+                        .push(parse(quote!(#(#attrs)* #name #colon_token #ty).into()).unwrap());
                     sub_param.push(it.clone());
-                    sub_args.push(name);
+                    sub_args.push(quote!(#(#cfgs)* #name));
                 }
             }
         }
     }
 
+    // Whether the helper can be an associated function, or next to the function
+    let sibling = options.sibling.is_some() || options.context == Context::Module;
+    let assoc = options.assoc.is_some() || options.context == Context::InherentImpl || {
+        let mut has_self = HasSelfType(false);
+        has_self.visit_signature(&sig);
+        has_self.visit_block(&block);
+        has_self.0
+    };
+
+    // A helper next to a free function does not need a predictable name, so make it
+    // unique to not shadow an item used in the body
+    let helper_name = if wrap_self || assoc {
+        unsafe_fn_name
+    } else {
+        let mut hasher = DefaultHasher::new();
+        quote!(#sig #block).to_string().hash(&mut hasher);
+        format_ident!("{}_{:x}", unsafe_fn_name, hasher.finish())
+    };
+
+    // Lints about the signature are already reported on the public function. The
+    // attributes come after, so that an `#[expect]` of such lint can be fulfilled.
     let fun = quote! {
-        #[doc(hide)]
-        #[inline]
-        #constness #asyncness #fn_token #unsafe_fn_name #impl_generics (#sub_param #variadic) #output #where_clause {
-            #block
-        }
+        #[doc(hidden)]
+        #inline
+        #[allow(clippy::too_many_arguments, clippy::needless_arbitrary_self_type)]
+        #(#body_attrs)*
+        #defaultness #constness #asyncness #fn_token #helper_name #impl_generics (#sub_param #variadic) #output #where_clause
+        #block
     };
 
+    // The public function only forwards to the helper, it can always be inlined,
+    // including across crates
     let fdecl = quote! {
-        #(#attrs)* #vis #constness #asyncness #unsafety #abi
+        #(#attrs)*
+        #[inline]
+        #vis #defaultness #constness #asyncness #unsafety #abi
         #fn_token #ident #impl_generics (#main_param #variadic) #output #where_clause
     };
 
-    let type_params: Vec<_> = generics.type_params().map(|x| &x.ident).collect();
-    let turbo = if type_params.is_empty() {
-        quote!()
+    let turbo = turbofish(generics);
+
+    // The public function of an async function awaits the future of the helper
+    let dot_await = asyncness.map(|_| quote!(.await));
+
+    // Reuse the braces of the original body for the public function, so that
+    // diagnostics about the whole function point to the user code
+    let with_body = |content: TokenStream2| {
+        let mut tokens = fdecl.clone();
+        block.brace_token.surround(&mut tokens, |t| {
+            t.extend(content);
+            t.extend(dot_await.clone())
+        });
+        tokens
+    };
+
+    // The body stays in the unsafe function when the place of a helper is not known: a
+    // nested function would not have access to the generic parameters of an impl, and
+    // `#[safe_body]` must work in the implementation of any trait. The lint then requires
+    // the unsafe operations to be within `unsafe` blocks, but it is not reported for the
+    // ones coming from a macro of another crate, so a helper is used whenever possible.
+    // With `#[target_feature]`, a method must also keep its body: in a trait implementation,
+    // the helper would be a safe method, which cannot have target features.
+    let target_feature = has_attribute(&attrs, "target_feature") && !sibling;
+    let in_place = (safe_body && options.context == Context::Unknown)
+        || naked
+        || target_feature
+        || options.in_place.is_some()
+        || !(wrap_self || assoc || sibling);
+
+    let r = if in_place {
+        quote! {
+            #(#all_attrs)*
+            #[deny(unsafe_op_in_unsafe_fn)]
+            #vis #defaultness #constness #asyncness #unsafety #abi
+            #fn_token #ident #impl_generics (#inputs #variadic) #output #where_clause
+            #block
+        }
+    } else if wrap_self {
+        let fdecl = with_body(quote!(self.#helper_name #turbo (#sub_args)));
+        quote!(#fun #fdecl)
+    } else if assoc {
+        let fdecl = with_body(quote!(Self::#helper_name #turbo (#sub_args)));
+        quote!(#fun #fdecl)
     } else {
-        quote!(::< #(#type_params),* >)
+        let fdecl = with_body(quote!(#helper_name #turbo (#sub_args)));
+        quote!(#fun #fdecl)
     };
 
-    let r = if wrap_self {
+    let safe_test = options.debug_safe_test.as_ref().map(|_| {
+        let safe_name = format_ident!("safe_{}", ident);
+        let call = if wrap_self {
+            quote!(self.#ident)
+        } else if assoc {
+            quote!(Self::#ident)
+        } else {
+            quote!(#ident)
+        };
+        let doc = format!(
+            "Safe wrapper around `{}` for the tests, only to be called with valid inputs",
+            ident
+        );
+        // The safe function is deprecated like the function, but calling it does not warn
+        let kept = attrs.iter().filter(|a| {
+            a.path.is_ident("must_use") || a.path.is_ident("deprecated") || a.path.is_ident("cfg")
+        });
         quote! {
-            #fun
-            #fdecl {
-                self.#unsafe_fn_name #turbo (#sub_args)
+            #[cfg(test)]
+            #[doc = #doc]
+            #(#kept)*
+            #[allow(deprecated)]
+            #[allow(dead_code)]
+            #vis #constness #asyncness #fn_token #safe_name #impl_generics (#main_param #variadic) #output #where_clause {
+                unsafe { #call #turbo (#sub_args) #dot_await }
             }
         }
-    } else if {
-        let mut has_self = HasSelfType(false);
-        has_self.visit_signature(&sig);
-        has_self.visit_block(&block);
-        has_self.0
-    } {
-        quote! {
-            #fun
-            #fdecl {
-                Self::#unsafe_fn_name #turbo (#sub_args)
+    });
+
+    //println!("{}", r);
+    quote!(#r #safe_test)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc_attrs(attrs: &[Attribute]) -> Vec<String> {
+        attrs
+            .iter()
+            .filter(|a| a.path.is_ident("doc"))
+            .map(|a| quote!(#a).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn doc_attributes_round_trip() {
+        let functions: [ItemFn; 2] = [
+            parse_quote! {
+                /// First line
+                ///
+                /// Second paragraph with `code`
+                #[doc = "explicit"]
+                #[must_use]
+                /** block comment */
+                pub fn documented(ptr: *const u32) -> u32 {
+                    unsafe { *ptr }
+                }
+            },
+            parse_quote! {
+                /// Method documentation
+                /// on two lines
+                pub fn documented(&self) -> u32 {
+                    self.0
+                }
+            },
+        ];
+        for f in functions {
+            let expected = doc_attrs(&f.attrs);
+            let output = unsafe_fn_impl(f.into(), Kind::UnsafeFn, &Options::default());
+            let file: File = parse2(output).unwrap();
+            let public = file
+                .items
+                .iter()
+                .find_map(|i| match i {
+                    Item::Fn(f) if f.sig.ident == "documented" => Some(f),
+                    _ => None,
+                })
+                .unwrap();
+            assert_eq!(doc_attrs(&public.attrs), expected);
+            // The helper is not documented
+            for i in &file.items {
+                if let Item::Fn(f) = i {
+                    if f.sig.ident != "documented" {
+                        assert_eq!(doc_attrs(&f.attrs), ["# [doc (hidden)]"]);
+                    }
+                }
             }
         }
-    } else {
-        quote!(
-            #fdecl {
-                #fun
-                #unsafe_fn_name #turbo (#sub_args)
+    }
+
+    #[test]
+    fn performance_hints() {
+        let f: ItemFn = parse_quote! {
+            #[cold]
+            #[inline(never)]
+            #[must_use]
+            fn hinted(&self) -> u32 {
+                self.0
+            }
+        };
+        let output = unsafe_fn_impl(f.into(), Kind::UnsafeFn, &Options::default());
+        let file: File = parse2(output).unwrap();
+        let attrs = |name: &str| {
+            file.items
+                .iter()
+                .find_map(|i| match i {
+                    Item::Fn(f) if f.sig.ident == name => Some(f),
+                    _ => None,
+                })
+                .unwrap()
+                .attrs
+                .iter()
+                .map(|a| quote!(#a).to_string())
+                .filter(|a| !a.contains("allow") && !a.contains("doc"))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(attrs("hinted"), ["# [must_use]", "# [inline]"]);
+        assert_eq!(
+            attrs("__unsafe_fn_hinted"),
+            ["# [cold]", "# [inline (never)]"]
+        );
+    }
+
+    /// The `#[inline]` of the public function makes it available to other crates. The
+    /// inlining itself across crates is not tested, since it needs a second crate.
+    #[test]
+    fn inline_delegate() {
+        let sibling = Options {
+            sibling: Some(parse_quote!(sibling)),
+            ..Options::default()
+        };
+        let functions: [(ItemFn, Options); 3] = [
+            (
+                parse_quote!(
+                    pub fn method(&self) -> u32 {
+                        self.0
+                    }
+                ),
+                Options::default(),
+            ),
+            (
+                parse_quote!(
+                    #[inline(never)]
+                    pub fn new() -> Self {
+                        Self(0)
+                    }
+                ),
+                Options::default(),
+            ),
+            (
+                parse_quote!(
+                    pub fn free(p: *const u32) -> u32 {
+                        unsafe { *p }
+                    }
+                ),
+                sibling,
+            ),
+        ];
+        for (f, options) in functions {
+            let name = f.sig.ident.clone();
+            let output = unsafe_fn_impl(f.into(), Kind::UnsafeFn, &options);
+            let file: File = parse2(output).unwrap();
+            let public = file
+                .items
+                .iter()
+                .find_map(|i| match i {
+                    Item::Fn(f) if f.sig.ident == name => Some(f),
+                    _ => None,
+                })
+                .unwrap();
+            let attrs: Vec<_> = public
+                .attrs
+                .iter()
+                .map(|a| quote!(#a).to_string())
+                .collect();
+            assert!(
+                attrs.contains(&"# [inline]".into()),
+                "{}: {:?}",
+                name,
+                attrs
+            );
+            assert!(
+                !attrs.iter().any(|a| a.contains("never")),
+                "{}: {:?}",
+                name,
+                attrs
+            );
+        }
+    }
+
+    #[test]
+    fn unsafe_attribute_routing() {
+        // `#[unsafe(link_section = ".ram")]` after `hide_unparsable`
+        let f: ItemFn = parse_quote! {
+            #[__unsafe_fn_unsafe_attr(link_section = ".ram")]
+            #[cfg_attr(test, __unsafe_fn_unsafe_attr(link_section = ".test_ram"))]
+            fn ram(&self) -> u32 {
+                self.0
             }
+        };
+        let output = unsafe_fn_impl(f.into(), Kind::UnsafeFn, &Options::default());
+        let file: File = parse2(output).unwrap();
+        for name in ["ram", "__unsafe_fn_ram"] {
+            let attrs: Vec<_> = file
+                .items
+                .iter()
+                .find_map(|i| match i {
+                    Item::Fn(f) if f.sig.ident == name => Some(f),
+                    _ => None,
+                })
+                .unwrap()
+                .attrs
+                .iter()
+                .map(|a| quote!(#a).to_string())
+                .filter(|a| a.contains("link_section"))
+                .collect();
+            assert_eq!(attrs.len(), 2, "{}: {:?}", name, attrs);
+        }
+    }
+
+    #[test]
+    fn hidden_placeholder() {
+        let m: TraitItemMethod = parse_quote! {
+            /// Documented method
+            fn documented(&self) -> u32;
+        };
+        let output = unsafe_fn_impl(m.into(), Kind::UnsafeFn, &Options::default());
+        let methods: Vec<TraitItemMethod> = parse::Parser::parse2(
+            |input: parse::ParseStream| {
+                let mut methods = Vec::new();
+                while !input.is_empty() {
+                    methods.push(input.parse()?);
+                }
+                Ok(methods)
+            },
+            output,
         )
-    };
-    //println!("{}", r);
-    r.into()
+        .unwrap();
+        assert_eq!(
+            doc_attrs(&methods[0].attrs),
+            ["# [doc = r\" Documented method\"]"]
+        );
+        assert_eq!(methods[1].sig.ident, "__unsafe_fn_documented");
+        assert_eq!(doc_attrs(&methods[1].attrs), ["# [doc (hidden)]"]);
+    }
 }
@@ -0,0 +1,10 @@
+//! A macro defined in another crate than the tests, for the UI tests: the compiler does
+//! not report the `unsafe_op_in_unsafe_fn` lint for the code of such macros.
+
+/// Dereference a raw pointer without `unsafe` block
+#[macro_export]
+macro_rules! deref {
+    ($p:expr) => {
+        *$p
+    };
+}
@@ -0,0 +1,80 @@
+use unsafe_fn::unsafe_fn;
+
+const trait ConstTrait {
+    #[unsafe_fn]
+    fn provided(&self) -> u32 {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        unsafe { self.required() + y + 1 }
+    }
+
+    #[unsafe_fn]
+    fn required(&self) -> u32;
+}
+
+struct ConstStruct(u32);
+
+impl const ConstTrait for ConstStruct {
+    #[unsafe_fn]
+    fn required(&self) -> u32 {
+        self.0
+    }
+}
+
+const PROVIDED: u32 = unsafe { ConstStruct(41).provided() };
+
+#[test]
+fn const_trait_method() {
+    assert_eq!(PROVIDED, 42);
+    assert_eq!(unsafe { ConstStruct(3).required() }, 3);
+}
+
+#[unsafe_fn]
+const fn call_required<T: [const] ConstTrait>(t: &T) -> u32 {
+    unsafe { t.required() * 2 }
+}
+
+const DOUBLED: u32 = unsafe { call_required(&ConstStruct(21)) };
+
+#[test]
+fn const_bound() {
+    assert_eq!(DOUBLED, 42);
+}
+
+#[unsafe_fn(sibling)]
+const fn call_sibling<T>(t: &T) -> u32
+where
+    T: [const] ConstTrait,
+{
+    unsafe { t.required() + 1 }
+}
+
+const trait Combine {
+    #[unsafe_fn]
+    fn combine<T: [const] ConstTrait>(&self, t: &T) -> u32;
+}
+
+impl const Combine for ConstStruct {
+    #[unsafe_fn]
+    fn combine<T: [const] ConstTrait>(&self, t: &T) -> u32 {
+        unsafe { self.0 + t.required() }
+    }
+}
+
+unsafe_fn::unsafe_fn_items! {
+    const fn from_items<T: ~const ConstTrait>(t: &T) -> u32 {
+        unsafe { t.required() }
+    }
+}
+
+const BOUNDS: [u32; 3] = unsafe {
+    [
+        call_sibling(&ConstStruct(1)),
+        ConstStruct(2).combine(&ConstStruct(3)),
+        from_items(&ConstStruct(4)),
+    ]
+};
+
+#[test]
+fn const_bound_helpers() {
+    assert_eq!(BOUNDS, [2, 5, 4]);
+}
@@ -0,0 +1,24 @@
+use unsafe_fn::unsafe_fn;
+
+struct Bytes(Vec<u8>);
+
+impl Bytes {
+    #[unsafe_fn]
+    fn chunk<const K: usize>(&self) -> [[u8; K]; 2]
+    where
+        [(); K * 2]:,
+    {
+        let mut flat = [0u8; K * 2];
+        flat.copy_from_slice(unsafe { self.0.get_unchecked(..K * 2) });
+        let mut result = [[0u8; K]; 2];
+        result[0].copy_from_slice(&flat[..K]);
+        result[1].copy_from_slice(&flat[K..]);
+        result
+    }
+}
+
+#[test]
+fn const_expr_where_clause() {
+    let b = Bytes(vec![1, 2, 3, 4, 5]);
+    assert_eq!(unsafe { b.chunk::<2>() }, [[1, 2], [3, 4]]);
+}
@@ -0,0 +1,16 @@
+//! Tests that require a nightly compiler: `cargo +nightly test --features nightly`
+#![cfg_attr(
+    feature = "nightly",
+    feature(const_trait_impl, generic_const_exprs, specialization)
+)]
+#![cfg_attr(feature = "nightly", allow(incomplete_features))]
+#![deny(unused_unsafe)]
+
+#[cfg(feature = "nightly")]
+mod const_trait;
+
+#[cfg(feature = "nightly")]
+mod generic_const_exprs;
+
+#[cfg(feature = "nightly")]
+mod specialization;
@@ -0,0 +1,42 @@
+use unsafe_fn::{safe_body, unsafe_fn};
+
+trait Process {
+    #[unsafe_fn]
+    fn process(&self) -> u32;
+
+    unsafe fn process_raw(&self) -> u32;
+}
+
+impl<T> Process for T {
+    #[unsafe_fn]
+    default fn process(&self) -> u32 {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        1 + y
+    }
+
+    #[safe_body]
+    default unsafe fn process_raw(&self) -> u32 {
+        unsafe { std::ptr::read(&10) }
+    }
+}
+
+impl Process for u8 {
+    #[unsafe_fn]
+    fn process(&self) -> u32 {
+        *self as u32
+    }
+
+    unsafe fn process_raw(&self) -> u32 {
+        20
+    }
+}
+
+#[test]
+fn default_fn() {
+    unsafe {
+        assert_eq!(().process(), 1);
+        assert_eq!(5u8.process(), 5);
+        assert_eq!(().process_raw(), 10);
+        assert_eq!(5u8.process_raw(), 20);
+    }
+}
@@ -0,0 +1,71 @@
+//! Check that the generated code does not require `std`
+#![no_std]
+#![deny(unused_unsafe)]
+#![allow(clippy::missing_safety_doc)]
+
+use unsafe_fn::unsafe_fn;
+
+#[unsafe_fn]
+fn read(ptr: *const u32) -> u32 {
+    unsafe { *ptr }
+}
+
+#[unsafe_fn]
+fn first<T: Copy>(arr: &[T]) -> T {
+    arr[0]
+}
+
+struct Holder<T>(T);
+
+impl<T: Copy> Holder<T> {
+    #[unsafe_fn]
+    fn get(&self) -> T {
+        self.0
+    }
+
+    #[unsafe_fn]
+    fn new(t: T) -> Self {
+        Self(t)
+    }
+}
+
+trait Tr {
+    #[unsafe_fn]
+    fn value(&self) -> u32;
+
+    #[unsafe_fn]
+    fn double(&self) -> u32 {
+        unsafe { self.value() * 2 }
+    }
+}
+
+impl Tr for u32 {
+    #[unsafe_fn]
+    fn value(&self) -> u32 {
+        *self
+    }
+}
+
+#[unsafe_fn(methods)]
+trait AllUnsafe {
+    fn raw(&self) -> u32;
+}
+
+unsafe impl AllUnsafe for u32 {
+    #[unsafe_fn]
+    fn raw(&self) -> u32 {
+        *self + 1
+    }
+}
+
+#[test]
+fn no_std() {
+    unsafe {
+        assert_eq!(read(&3), 3);
+        assert_eq!(first(&[4u8, 5]), 4);
+        assert_eq!(Holder::new(5u16).get(), 5);
+        assert_eq!(6u32.value(), 6);
+        assert_eq!(6u32.double(), 12);
+        assert_eq!(7u32.raw(), 8);
+    }
+}
@@ -1,4 +1,4 @@
-#![deny(unused_unsafe)]
+#![deny(unused_unsafe, unused_attributes)]
 
 use unsafe_fn::{safe_body, unsafe_fn};
 
@@ -9,6 +9,7 @@ struct SomeStruct {
 }
 
 #[unsafe_fn]
+#[allow(clippy::disallowed_names)]
 fn hello(x: u32, foo: String) -> u32 {
     let y: u32 = unsafe { std::mem::zeroed() };
     y + x + foo.len() as u32
@@ -22,6 +23,7 @@ fn plus_one(mut val: u32, _x: String) -> u32 {
 }
 
 #[unsafe_fn]
+#[allow(clippy::disallowed_names)]
 fn take_struct(
     SomeStruct { mut i, s }: SomeStruct,
     foo @ SomeStruct { .. }: &mut SomeStruct,
@@ -35,6 +37,7 @@ fn take_struct(
     }
 }
 
+#[allow(dead_code)]
 impl SomeStruct {
     #[unsafe_fn]
     fn i_plus(&self, plus: u32) -> u32 {
@@ -43,6 +46,7 @@ impl SomeStruct {
     }
 
     #[unsafe_fn]
+    #[allow(clippy::boxed_local)]
     fn i_plus_box(self: Box<Self>, plus: u32) -> u32 {
         unsafe { self.i_plus(plus) }
     }
@@ -90,6 +94,7 @@ impl SomeStruct {
 
 pub struct StructWithGen<'a, T>(&'a T);
 
+#[allow(dead_code)]
 impl<'a, T: 'a> StructWithGen<'a, T> {
     #[unsafe_fn]
     fn new_t() -> T
@@ -147,11 +152,13 @@ fn with_return(x: &mut u32, cond: bool) {
 }
 
 #[unsafe_fn]
+#[allow(clippy::missing_safety_doc)]
 trait Marker {}
 
 unsafe impl Marker for SomeStruct {}
 
 #[test]
+#[allow(unused_allocation)]
 fn test_unsafe_fn1() {
     assert_eq!(unsafe { hello(42, "XYZ".into()) }, 42 + 3);
     assert_eq!(unsafe { plus_one(42, "XYZ".into()) }, 42 + 1);
@@ -198,12 +205,44 @@ fn test_safe_body1() {
 #[test]
 fn raw_identifier() {
     #[unsafe_fn]
+    #[allow(clippy::needless_return)]
     fn r#unsafe(r#u32: u32) -> u32 {
         return r#u32;
     }
     assert_eq!(unsafe { r#unsafe(5) }, 5);
 }
 
+#[unsafe_fn]
+fn r#async(x: u32) -> u32 {
+    let y: u32 = unsafe { std::mem::zeroed() };
+    x + y
+}
+
+#[unsafe_fn]
+fn r#dyn<T: Default>() -> T {
+    T::default()
+}
+
+impl SomeStruct {
+    #[unsafe_fn]
+    fn r#try(&self) -> u32 {
+        self.i
+    }
+
+    #[unsafe_fn]
+    fn r#dyn() -> Self {
+        Self::default()
+    }
+}
+
+#[test]
+fn raw_keyword_identifiers() {
+    assert_eq!(unsafe { r#async(3) }, 3);
+    assert_eq!(unsafe { r#dyn::<u32>() }, 0);
+    assert_eq!(unsafe { SomeStruct::r#dyn().r#try() }, 0);
+}
+
+#[allow(dead_code)]
 trait SomeTrait {
     #[unsafe_fn]
     fn foo(&self) -> u32;
@@ -237,3 +276,2722 @@ impl SomeTrait for u32 {
         R::default()
     }
 }
+
+trait ExoticSelf {
+    #[unsafe_fn]
+    fn sum(&self) -> u32;
+    #[unsafe_fn]
+    fn make() -> Self;
+}
+
+impl ExoticSelf for (u32, u32) {
+    #[unsafe_fn]
+    fn sum(&self) -> u32 {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        self.0 + self.1 + y
+    }
+    #[unsafe_fn]
+    fn make() -> Self {
+        (1, 2)
+    }
+}
+
+impl ExoticSelf for [u8; 4] {
+    #[unsafe_fn]
+    fn sum(&self) -> u32 {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        self.iter().map(|x| *x as u32).sum::<u32>() + y
+    }
+    #[unsafe_fn]
+    fn make() -> Self {
+        [1, 2, 3, 4]
+    }
+}
+
+#[test]
+fn exotic_self_types() {
+    assert_eq!(unsafe { (3u32, 4u32).sum() }, 7);
+    assert_eq!(unsafe { <(u32, u32)>::make() }, (1, 2));
+    assert_eq!(unsafe { [1u8, 2, 3, 4].sum() }, 10);
+    assert_eq!(unsafe { <[u8; 4]>::make().sum() }, 10);
+}
+
+#[unsafe_fn]
+#[allow(clippy::too_many_arguments)]
+fn wide(a: u8, b: u8, c: u8, d: u8, e: u8, f: u8, g: u8, h: u8) -> u32 {
+    let y: u32 = unsafe { std::mem::zeroed() };
+    [a, b, c, d, e, f, g, h]
+        .iter()
+        .map(|x| *x as u32)
+        .sum::<u32>()
+        + y
+}
+
+impl SomeStruct {
+    #[unsafe_fn]
+    #[allow(clippy::too_many_arguments)]
+    fn wide_method(&self, a: u8, b: u8, c: u8, d: u8, e: u8, f: u8, g: u8) -> u32 {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        self.i + [a, b, c, d, e, f, g].iter().map(|x| *x as u32).sum::<u32>() + y
+    }
+}
+
+#[test]
+fn too_many_arguments() {
+    assert_eq!(unsafe { wide(1, 2, 3, 4, 5, 6, 7, 8) }, 36);
+    let s = SomeStruct {
+        i: 1,
+        s: String::new(),
+    };
+    assert_eq!(unsafe { s.wide_method(1, 2, 3, 4, 5, 6, 7) }, 29);
+}
+
+impl SomeStruct {
+    #[unsafe_fn]
+    fn same_i<Rhs = Self>(&self, other: &Rhs) -> bool
+    where
+        Rhs: Clone + Into<SomeStruct>,
+    {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        self.i + y == other.clone().into().i
+    }
+
+    #[unsafe_fn]
+    fn from_other<Rhs = Self>(other: Rhs) -> u32
+    where
+        Rhs: Into<SomeStruct>,
+    {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        other.into().i + y
+    }
+}
+
+impl<'a, T: 'a + PartialEq> StructWithGen<'a, T> {
+    #[unsafe_fn]
+    fn same_as<Rhs = Self>(&self, other: &Rhs) -> bool
+    where
+        Rhs: AsRef<T>,
+    {
+        let _: u32 = unsafe { std::mem::zeroed() };
+        self.0 == other.as_ref()
+    }
+
+    #[unsafe_fn]
+    fn wrap<Rhs = Self>(x: &'a T, _: Option<Rhs>) -> Self {
+        let _: u32 = unsafe { std::mem::zeroed() };
+        StructWithGen(x)
+    }
+}
+
+impl<'a, T> AsRef<T> for StructWithGen<'a, T> {
+    fn as_ref(&self) -> &T {
+        self.0
+    }
+}
+
+#[test]
+fn default_self_type_param() {
+    let s = SomeStruct {
+        i: 5,
+        s: String::new(),
+    };
+    assert!(unsafe { s.same_i(&s) });
+    assert!(unsafe { s.same_i::<SomeStruct>(&s.clone()) });
+    assert_eq!(unsafe { SomeStruct::from_other(s.clone()) }, 5);
+
+    let x = 42;
+    let g = StructWithGen(&x);
+    assert!(unsafe { g.same_as(&StructWithGen(&42)) });
+    let w = unsafe { StructWithGen::wrap(&x, None::<StructWithGen<u32>>) };
+    assert!(unsafe { w.same_as(&g) });
+}
+
+impl SomeStruct {
+    #[unsafe_fn]
+    fn pick<'a, T>(&'a self, items: &'a [T]) -> &'a T
+    where
+        T: Ord,
+    {
+        let y: usize = unsafe { std::mem::zeroed() };
+        items
+            .iter()
+            .max()
+            .unwrap_or_else(|| &items[self.i as usize + y])
+    }
+}
+
+#[test]
+fn shared_lifetime_and_bound() {
+    let s = SomeStruct::default();
+    let items = vec![3, 9, 2];
+    let max = unsafe { s.pick(&items) };
+    assert_eq!(*max, 9);
+    assert!(std::ptr::eq(max, &items[1]));
+}
+
+unsafe_fn::unsafe_mod! {
+    mod unsafe_module {
+        pub fn read(ptr: *const u32) -> u32 {
+            unsafe { *ptr }
+        }
+
+        pub unsafe fn already_unsafe(ptr: *const u32) -> u32 {
+            *ptr
+        }
+
+        pub struct NotAFunction;
+
+        pub mod nested {
+            pub fn add<T: Into<u32>>(a: T, b: u32) -> u32 {
+                let y: u32 = unsafe { std::mem::zeroed() };
+                a.into() + b + y
+            }
+        }
+    }
+}
+
+#[test]
+fn unsafe_mod() {
+    let x = 12;
+    assert_eq!(unsafe { unsafe_module::read(&x) }, 12);
+    assert_eq!(unsafe { unsafe_module::already_unsafe(&x) }, 12);
+    assert_eq!(unsafe { unsafe_module::nested::add(4u8, 5) }, 9);
+    let _ = unsafe_module::NotAFunction;
+}
+
+struct Stack<T>(Vec<T>);
+
+impl<T> Stack<T> {
+    #[unsafe_fn]
+    fn get(&self, i: usize) -> Option<&T> {
+        if i < self.0.len() {
+            Some(unsafe { self.0.get_unchecked(i) })
+        } else {
+            None
+        }
+    }
+
+    #[unsafe_fn]
+    fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        if i < self.0.len() {
+            Some(unsafe { self.0.get_unchecked_mut(i) })
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn optional_reference() {
+    let mut s = Stack(vec![1, 2, 3]);
+    assert_eq!(unsafe { s.get(1) }, Some(&2));
+    assert_eq!(unsafe { s.get(3) }, None);
+    if let Some(x) = unsafe { s.get_mut(2) } {
+        *x = 30;
+    }
+    assert!(unsafe { s.get_mut(5) }.is_none());
+    let r = unsafe { s.get(2) };
+    assert_eq!(r, Some(&30));
+}
+
+// The attributes are not yet evaluated within a function-like macro
+unsafe_fn::unsafe_mod! {
+    mod cfg_attr_module {
+        #[cfg_attr(test, inline(never), must_use)]
+        pub fn in_test() -> bool {
+            let y: u32 = unsafe { std::mem::zeroed() };
+            cfg!(test) && y == 0
+        }
+
+        #[cfg_attr(not(test), inline(always))]
+        pub fn not_in_test(x: u32) -> u32 {
+            x + 1
+        }
+    }
+}
+
+#[test]
+fn cfg_attr_routing() {
+    assert!(unsafe { cfg_attr_module::in_test() });
+    assert_eq!(unsafe { cfg_attr_module::not_in_test(1) }, 2);
+}
+
+struct Buf<'a, const N: usize>(&'a [u8; N]);
+
+impl<'a, const N: usize> Buf<'a, N> {
+    #[unsafe_fn]
+    fn capacity(&self) -> usize {
+        let y: usize = unsafe { std::mem::zeroed() };
+        N + y
+    }
+
+    #[unsafe_fn]
+    fn first(&self) -> &'a u8 {
+        let data: &'a [u8; N] = self.0;
+        unsafe { data.get_unchecked(0) }
+    }
+}
+
+#[test]
+fn impl_lifetime_and_const_generic() {
+    let data = [7, 8, 9];
+    let b = Buf(&data);
+    assert_eq!(unsafe { b.capacity() }, 3);
+    assert_eq!(unsafe { *b.first() }, 7);
+}
+
+impl<T> Stack<T> {
+    #[unsafe_fn]
+    fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        let vec = &mut self.0;
+        let len = vec.len();
+        // The elements are moved out by the iterator
+        unsafe { vec.set_len(0) };
+        (0..len).map(move |i| unsafe { vec.as_ptr().add(i).read() })
+    }
+}
+
+#[test]
+fn iterator_borrowing_mut_self() {
+    let mut s = Stack(vec![String::from("a"), String::from("b")]);
+    let drained: Vec<_> = unsafe { s.drain() }.collect();
+    assert_eq!(drained, ["a", "b"]);
+    assert!(s.0.is_empty());
+}
+
+#[unsafe_fn(methods)]
+#[allow(clippy::missing_safety_doc)]
+trait UnsafeMethods {
+    fn required(&self) -> u32;
+
+    fn provided(&self) -> u32 {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        unsafe { self.required() + y + 1 }
+    }
+
+    fn provided_static() -> u32 {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        y + 10
+    }
+
+    unsafe fn already_unsafe(&self) -> u32 {
+        self.required()
+    }
+
+    // The options of the method override the ones of the trait
+    #[unsafe_fn(require_unsafe)]
+    fn checked(&self) -> u32 {
+        unsafe { self.required() }
+    }
+}
+
+unsafe impl UnsafeMethods for SomeStruct {
+    #[unsafe_fn]
+    fn required(&self) -> u32 {
+        self.i
+    }
+}
+
+unsafe impl UnsafeMethods for u32 {
+    #[unsafe_fn]
+    fn required(&self) -> u32 {
+        *self
+    }
+
+    #[unsafe_fn]
+    fn provided(&self) -> u32 {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        *self + y + 2
+    }
+}
+
+#[test]
+fn unsafe_trait_methods() {
+    let s = SomeStruct {
+        i: 4,
+        s: String::new(),
+    };
+    assert_eq!(unsafe { s.required() }, 4);
+    assert_eq!(unsafe { s.provided() }, 5);
+    assert_eq!(unsafe { s.already_unsafe() }, 4);
+    assert_eq!(unsafe { s.checked() }, 4);
+    assert_eq!(
+        unsafe { <SomeStruct as UnsafeMethods>::provided_static() },
+        10
+    );
+    assert_eq!(unsafe { UnsafeMethods::provided(&7u32) }, 9);
+}
+
+#[unsafe_fn]
+fn statement_attribute(x: u32) -> u32 {
+    #[allow(unused_unsafe)]
+    let y = unsafe { x + 1 };
+    let z: u32 = unsafe { std::mem::zeroed() };
+    y + z
+}
+
+#[test]
+fn statement_level_allow() {
+    assert_eq!(unsafe { statement_attribute(1) }, 2);
+}
+
+impl<'a, T> StructWithGen<'a, T> {
+    #[unsafe_fn]
+    #[allow(clippy::needless_arbitrary_self_type)]
+    fn inner_ref<'b>(self: &'b Self) -> &'b &'a T
+    where
+        Self: 'b,
+    {
+        let _: u32 = unsafe { std::mem::zeroed() };
+        &self.0
+    }
+}
+
+#[test]
+fn explicit_self_with_outlives_bound() {
+    let x = 5;
+    let g = StructWithGen(&x);
+    let r: &&u32 = unsafe { g.inner_ref() };
+    assert_eq!(**r, 5);
+}
+
+#[unsafe_fn]
+fn call_generic_trait_method<T: SomeTrait>(t: &T) -> u32 {
+    let y: u32 = unsafe { std::mem::zeroed() };
+    unsafe { t.foo() + T::bar(t) + y }
+}
+
+#[unsafe_fn]
+fn call_generic_static_method<T>() -> u32
+where
+    T: UnsafeMethods,
+{
+    unsafe { T::provided_static() }
+}
+
+#[test]
+fn generic_bound_with_unsafe_fn_method() {
+    assert_eq!(unsafe { call_generic_trait_method(&3u32) }, 3 + 5);
+    assert_eq!(unsafe { call_generic_static_method::<u32>() }, 10);
+}
+
+impl SomeStruct {
+    #[unsafe_fn]
+    fn as_any(&self) -> &dyn std::any::Any {
+        let _: u32 = unsafe { std::mem::zeroed() };
+        self
+    }
+
+    #[unsafe_fn]
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+impl<T: 'static> Stack<T> {
+    #[unsafe_fn]
+    fn as_any(&self) -> &dyn std::any::Any {
+        let _: u32 = unsafe { std::mem::zeroed() };
+        self
+    }
+
+    #[unsafe_fn]
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+#[test]
+fn dyn_any() {
+    let mut s = SomeStruct {
+        i: 3,
+        s: String::new(),
+    };
+    assert_eq!(
+        unsafe { s.as_any() }
+            .downcast_ref::<SomeStruct>()
+            .unwrap()
+            .i,
+        3
+    );
+    unsafe { s.as_any_mut() }
+        .downcast_mut::<SomeStruct>()
+        .unwrap()
+        .i = 4;
+    assert_eq!(s.i, 4);
+
+    let mut st = Stack(vec![1u8]);
+    assert!(unsafe { st.as_any() }
+        .downcast_ref::<SomeStruct>()
+        .is_none());
+    unsafe { st.as_any_mut() }
+        .downcast_mut::<Stack<u8>>()
+        .unwrap()
+        .0
+        .push(2);
+    assert_eq!(st.0, [1, 2]);
+}
+
+fn __unsafe_fn_collide() -> u32 {
+    7
+}
+
+#[unsafe_fn]
+fn collide() -> u32 {
+    __unsafe_fn_collide() + 1
+}
+
+#[test]
+fn helper_naming() {
+    // The helper of a free function does not shadow items with the same name
+    assert_eq!(unsafe { collide() }, 8);
+    // The helper of trait methods has a deterministic name so that it matches the
+    // placeholder of the trait declaration
+    assert_eq!(SomeTrait::__unsafe_fn_foo(&3u32), 3);
+}
+
+#[unsafe_fn]
+fn noop() {}
+
+impl SomeStruct {
+    #[unsafe_fn]
+    fn noop(&self) {}
+}
+
+#[unsafe_fn(require_unsafe)]
+fn with_required_unsafe(ptr: *const u32) -> u32 {
+    unsafe { *ptr }
+}
+
+// The unsafe block is only within a macro invocation
+#[unsafe_fn(require_unsafe)]
+fn check_pointee(ptr: *const u32) {
+    assert_eq!(unsafe { *ptr }, 3);
+}
+
+#[test]
+fn empty_body() {
+    unsafe { noop() };
+    unsafe { SomeStruct::default().noop() };
+    assert_eq!(unsafe { with_required_unsafe(&3) }, 3);
+    unsafe { check_pointee(&3) };
+}
+
+#[derive(Default)]
+struct Counter<T>(u8, std::marker::PhantomData<T>);
+
+impl<T> Iterator for Counter<T> {
+    type Item = u8;
+    fn next(&mut self) -> Option<u8> {
+        self.0 += 1;
+        Some(self.0)
+    }
+}
+
+impl<T> Counter<T> {
+    #[unsafe_fn]
+    fn next_unchecked(&mut self) -> u8
+    where
+        Self: Iterator<Item = u8>,
+    {
+        unsafe { self.next().unwrap_unchecked() }
+    }
+
+    #[unsafe_fn]
+    fn first() -> u8
+    where
+        Self: Iterator<Item = u8> + Default,
+    {
+        let mut it = <Self as Default>::default();
+        unsafe { it.next().unwrap_unchecked() }
+    }
+
+    // `Self` is only in the where clause, but that is enough to have access to `T`
+    #[unsafe_fn]
+    fn item_size() -> usize
+    where
+        Self: Iterator<Item = u8>,
+    {
+        let y: usize = unsafe { std::mem::zeroed() };
+        std::mem::size_of::<T>() + y
+    }
+}
+
+#[test]
+fn self_in_where_clause_equality() {
+    let mut c = Counter::<()>::default();
+    assert_eq!(unsafe { c.next_unchecked() }, 1);
+    assert_eq!(unsafe { c.next_unchecked() }, 2);
+    assert_eq!(unsafe { Counter::<u32>::first() }, 1);
+    assert_eq!(unsafe { Counter::<u32>::item_size() }, 4);
+}
+
+#[unsafe_fn(sibling)]
+fn sibling_helper<T: Default + Into<u64>>(ptr: *const u64) -> u64 {
+    let x = unsafe { *ptr };
+    x + T::default().into()
+}
+
+#[unsafe_fn(sibling)]
+fn sibling_with_return(x: &mut u32, cond: bool) {
+    *x += 4;
+    if cond {
+        return;
+    }
+    *x += 8;
+}
+
+#[test]
+fn sibling() {
+    assert_eq!(unsafe { sibling_helper::<u8>(&5) }, 5);
+    let mut m = 1;
+    unsafe { sibling_with_return(&mut m, false) };
+    assert_eq!(m, 1 + 4 + 8);
+}
+
+impl<'a, const N: usize> Buf<'a, N> {
+    #[unsafe_fn]
+    fn to_array(&self) -> [u8; N] {
+        let mut result = [0; N];
+        for (i, x) in result.iter_mut().enumerate() {
+            *x = unsafe { *self.0.get_unchecked(i) };
+        }
+        result
+    }
+}
+
+#[test]
+fn return_type_with_impl_const_generic() {
+    let data = [1, 2, 3, 4];
+    assert_eq!(unsafe { Buf(&data).to_array() }, data);
+}
+
+impl SomeStruct {
+    #[unsafe_fn]
+    fn split(self) -> (String, u32) {
+        let s = self.s;
+        let y: u32 = unsafe { std::mem::zeroed() };
+        (s, self.i + y)
+    }
+}
+
+#[test]
+fn partial_move_of_self() {
+    let s = SomeStruct {
+        i: 2,
+        s: "abc".into(),
+    };
+    assert_eq!(unsafe { s.split() }, ("abc".into(), 2));
+}
+
+#[unsafe_fn]
+#[doc = include_str!("safety.md")]
+pub fn documented_by_file(ptr: *const u32) -> u32 {
+    unsafe { *ptr }
+}
+
+#[test]
+fn doc_include_str() {
+    assert_eq!(unsafe { documented_by_file(&4) }, 4);
+}
+
+extern "C" fn double(x: u32) -> u32 {
+    x * 2
+}
+
+unsafe extern "C" fn read_double(x: *const u32) -> u32 {
+    *x * 2
+}
+
+#[unsafe_fn]
+fn register(
+    cb: extern "C" fn(u32) -> u32,
+    unsafe_cb: unsafe extern "C" fn(*const u32) -> u32,
+) -> u32 {
+    let x = cb(3);
+    x + unsafe { unsafe_cb(&x) }
+}
+
+#[test]
+fn fn_pointer_with_abi() {
+    assert_eq!(unsafe { register(double, read_double) }, 6 + 12);
+}
+
+#[unsafe_fn]
+fn transform(it: impl Iterator<Item = u8>) -> impl Iterator<Item = u8> {
+    it.map(|x| unsafe { x.unchecked_add(1) })
+}
+
+impl SomeStruct {
+    #[unsafe_fn]
+    fn transform(&self, it: impl Iterator<Item = u32>) -> impl Iterator<Item = u32> {
+        let i = self.i;
+        it.map(move |x| x + i)
+    }
+}
+
+#[test]
+fn impl_trait_argument_and_return() {
+    let v: Vec<_> = unsafe { transform(vec![1, 2].into_iter()) }.collect();
+    assert_eq!(v, [2, 3]);
+    let s = SomeStruct {
+        i: 10,
+        s: String::new(),
+    };
+    let v: Vec<_> = unsafe { s.transform(0..2) }.collect();
+    assert_eq!(v, [10, 11]);
+}
+
+impl SomeStruct {
+    #[unsafe_fn]
+    fn with_field(self, i: u32) -> Self {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        Self { i: i + y, ..self }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Pair<T> {
+    first: T,
+    second: T,
+}
+
+impl<T> Pair<T> {
+    #[unsafe_fn]
+    fn with_first(self, first: T) -> Self {
+        let _: u32 = unsafe { std::mem::zeroed() };
+        Self { first, ..self }
+    }
+}
+
+#[test]
+fn struct_update_from_self() {
+    let s = SomeStruct {
+        i: 1,
+        s: "x".into(),
+    };
+    let s = unsafe { s.with_field(5) };
+    assert_eq!((s.i, s.s.as_str()), (5, "x"));
+
+    let p = Pair {
+        first: "a",
+        second: "b",
+    };
+    assert_eq!(
+        unsafe { p.with_first("c") },
+        Pair {
+            first: "c",
+            second: "b"
+        }
+    );
+}
+
+#[unsafe_fn]
+fn closure_annotation<T: Clone>(x: T) -> T {
+    let f: fn(T) -> T = |x| x;
+    let _: u32 = unsafe { std::mem::zeroed() };
+    f(x.clone())
+}
+
+impl<T: Clone> Stack<T> {
+    #[unsafe_fn(assoc)]
+    fn identity(x: T) -> T {
+        let f: fn(T) -> T = |x| x;
+        let _: u32 = unsafe { std::mem::zeroed() };
+        f(x)
+    }
+}
+
+#[test]
+fn generic_in_closure_annotation() {
+    assert_eq!(unsafe { closure_annotation(5) }, 5);
+    assert_eq!(unsafe { Stack::<&str>::identity("x") }, "x");
+}
+
+static TABLE: [u8; 3] = [1, 2, 3];
+static mut COUNTER: u32 = 0;
+
+#[unsafe_fn]
+fn table(n: usize) -> &'static [u8] {
+    let y: usize = unsafe { std::mem::zeroed() };
+    &TABLE[..n + y]
+}
+
+#[unsafe_fn]
+fn increment_counter() -> &'static u32 {
+    unsafe {
+        COUNTER += 1;
+        &*std::ptr::addr_of!(COUNTER)
+    }
+}
+
+#[test]
+fn static_data() {
+    assert_eq!(unsafe { table(2) }, [1, 2]);
+    assert_eq!(unsafe { *increment_counter() }, 1);
+}
+
+trait Get<T> {
+    #[unsafe_fn]
+    fn get(&self) -> T;
+}
+
+struct Wrapper<T>(T);
+
+impl<T: Copy> Get<T> for Wrapper<T> {
+    #[unsafe_fn]
+    fn get(&self) -> T {
+        let value: T = self.0;
+        let _: u32 = unsafe { std::mem::zeroed() };
+        value
+    }
+}
+
+impl<T: Copy> Get<T> for Pair<T> {
+    #[unsafe_fn]
+    fn get(&self) -> T {
+        unsafe { Wrapper(self.first).get() }
+    }
+}
+
+#[test]
+fn trait_impl_for_generic_type() {
+    assert_eq!(unsafe { Wrapper(3u8).get() }, 3);
+    let p = Pair {
+        first: 'a',
+        second: 'b',
+    };
+    assert_eq!(unsafe { p.get() }, 'a');
+}
+
+impl SomeStruct {
+    #[unsafe_fn]
+    #[allow(unreachable_code)]
+    fn diverging(&self) -> u32 {
+        if self.i > 0 {
+            return self.i;
+        }
+        panic!("diverging");
+        0
+    }
+}
+
+#[unsafe_fn]
+#[allow(unreachable_code)]
+fn diverging_free(x: u32) -> u32 {
+    return x;
+    x + 1
+}
+
+#[test]
+fn allow_unreachable_code() {
+    let s = SomeStruct {
+        i: 1,
+        s: String::new(),
+    };
+    assert_eq!(unsafe { s.diverging() }, 1);
+    assert_eq!(unsafe { diverging_free(3) }, 3);
+}
+
+#[repr(transparent)]
+struct Meters(u32);
+
+#[repr(transparent)]
+struct Feet(u32);
+
+impl Meters {
+    #[unsafe_fn]
+    fn as_feet(&self) -> &Feet {
+        unsafe { std::mem::transmute::<&Meters, &Feet>(self) }
+    }
+}
+
+#[test]
+fn transmute_reference() {
+    let m = Meters(3);
+    let f = unsafe { m.as_feet() };
+    assert_eq!(f.0, 3);
+    assert!(std::ptr::eq(f as *const Feet as *const u32, &m.0));
+}
+
+// The public function is always #[inline], this must not conflict with the
+// inline attributes of the user
+#[unsafe_fn]
+#[inline]
+#[allow(clippy::missing_safety_doc)]
+pub fn inline_hint(x: u32) -> u32 {
+    x + 1
+}
+
+impl SomeStruct {
+    #[unsafe_fn]
+    #[inline(always)]
+    pub fn inline_always(&self) -> u32 {
+        self.i + 1
+    }
+}
+
+#[test]
+fn inline_delegate() {
+    assert_eq!(unsafe { inline_hint(1) }, 2);
+    assert_eq!(unsafe { SomeStruct::default().inline_always() }, 1);
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+struct Cell<T>(T);
+
+impl<T: Copy> Cell<T> {
+    fn new(x: T) -> Self {
+        Cell(x)
+    }
+
+    // `Self` is only used in the body
+    #[unsafe_fn]
+    fn repeat<const N: usize>(x: T) -> [T; N] {
+        let cells = [Self::new(x); N];
+        let _: u32 = unsafe { std::mem::zeroed() };
+        cells.map(|c| c.0)
+    }
+}
+
+#[test]
+fn self_in_array_repeat() {
+    assert_eq!(unsafe { Cell::repeat::<3>(7u8) }, [7, 7, 7]);
+}
+
+#[unsafe_fn]
+fn convert_all<T, U>(items: Vec<T>) -> (Vec<U>, U)
+where
+    T: Into<U>,
+    U: Default,
+{
+    let converted = items.into_iter().map(Into::into).collect();
+    let default = unsafe { std::mem::MaybeUninit::new(U::default()).assume_init() };
+    (converted, default)
+}
+
+#[test]
+fn where_clause_with_multiple_generics() {
+    let (v, d) = unsafe { convert_all::<u8, u32>(vec![1, 2]) };
+    assert_eq!(v, [1u32, 2]);
+    assert_eq!(d, 0);
+}
+
+impl SomeStruct {
+    #[unsafe_fn]
+    fn closure(&self) -> impl Fn() -> u32 + '_ {
+        move || unsafe { self.i_plus(1) }
+    }
+}
+
+#[test]
+fn closure_capturing_self() {
+    let s = SomeStruct {
+        i: 2,
+        s: String::new(),
+    };
+    let f = unsafe { s.closure() };
+    assert_eq!(f(), 3);
+    assert_eq!(f(), 3);
+}
+
+trait RawIter {
+    type Item;
+    #[unsafe_fn]
+    fn next_raw(&mut self) -> Option<Self::Item>;
+}
+
+impl<T: Copy> RawIter for Stack<T> {
+    type Item = T;
+    #[unsafe_fn]
+    fn next_raw(&mut self) -> Option<Self::Item> {
+        let len = self.0.len();
+        if len == 0 {
+            return None;
+        }
+        unsafe { self.0.set_len(len - 1) };
+        Some(unsafe { *self.0.as_ptr().add(len - 1) })
+    }
+}
+
+impl<T> Counter<T> {
+    #[unsafe_fn]
+    fn next_item(&mut self) -> Option<<Self as Iterator>::Item> {
+        unsafe { Some(self.next_unchecked()) }
+    }
+}
+
+#[test]
+fn associated_type_projection() {
+    let mut s = Stack(vec![1, 2]);
+    assert_eq!(unsafe { s.next_raw() }, Some(2));
+    assert_eq!(unsafe { s.next_raw() }, Some(1));
+    assert_eq!(unsafe { s.next_raw() }, None);
+    let mut c = Counter::<()>::default();
+    assert_eq!(unsafe { c.next_item() }, Some(1));
+}
+
+#[unsafe_fn(safety_comment)]
+fn justified(ptr: *const u32) -> u32 {
+    let x = unsafe {
+        unsafe_fn::safety!("the caller guarantees that ptr is valid");
+        *ptr
+    };
+    let y: u32 = unsafe {
+        unsafe_fn::safety!("u32 can be zeroed");
+        std::mem::zeroed()
+    };
+    let v = vec![unsafe {
+        unsafe_fn::safety!("the caller guarantees that ptr is valid");
+        *ptr
+    }];
+    assert_eq!(v, [x]);
+    x + y
+}
+
+#[test]
+fn safety_comment() {
+    assert_eq!(unsafe { justified(&5) }, 5);
+}
+
+#[unsafe_fn]
+fn get_unchecked_wrapper<T>(s: &[T], i: usize) -> &T {
+    let index = i.min(s.len() - 1);
+    unsafe { s.get_unchecked(index) }
+}
+
+struct SliceWrapper<'a, T>(&'a [T]);
+
+impl<'a, T> SliceWrapper<'a, T> {
+    #[unsafe_fn]
+    fn get_unchecked(&self, i: usize) -> &'a T {
+        let slice: &'a [T] = self.0;
+        unsafe { slice.get_unchecked(i) }
+    }
+}
+
+#[test]
+fn slice_get_unchecked() {
+    let v = vec![1, 2, 3];
+    let r = unsafe { get_unchecked_wrapper(&v, 5) };
+    assert_eq!(*r, 3);
+    // The reference outlives the wrapper
+    let r = {
+        let w = SliceWrapper(&v);
+        unsafe { w.get_unchecked(1) }
+    };
+    assert_eq!(*r, 2);
+}
+
+impl<T: Clone> Pair<T> {
+    #[unsafe_fn]
+    fn clones(&self, n: usize) -> Vec<Box<Self>> {
+        let _: u32 = unsafe { std::mem::zeroed() };
+        (0..n)
+            .map(|_| {
+                Box::new(Pair {
+                    first: self.first.clone(),
+                    second: self.second.clone(),
+                })
+            })
+            .collect()
+    }
+
+    #[unsafe_fn]
+    fn boxed_pairs(first: T, second: T) -> Vec<Box<Self>> {
+        vec![Box::new(Pair { first, second })]
+    }
+}
+
+#[test]
+fn nested_self_in_return_type() {
+    let p = Pair {
+        first: 1,
+        second: 2,
+    };
+    let v = unsafe { p.clones(2) };
+    assert_eq!(v.len(), 2);
+    assert_eq!(*v[1], p);
+    assert_eq!(*unsafe { Pair::boxed_pairs(1, 2) }[0], p);
+}
+
+#[unsafe_fn(debug_safe_test)]
+fn read_tested(ptr: *const u32) -> u32 {
+    unsafe { *ptr }
+}
+
+impl SomeStruct {
+    #[unsafe_fn(debug_safe_test)]
+    fn plus_tested<T: Into<u32>>(&self, x: T) -> u32 {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        self.i + x.into() + y
+    }
+
+    #[unsafe_fn(debug_safe_test)]
+    fn new_tested(i: u32) -> Self {
+        Self {
+            i,
+            ..Self::default()
+        }
+    }
+}
+
+#[test]
+fn debug_safe_test() {
+    assert_eq!(safe_read_tested(&4), 4);
+    assert_eq!(unsafe { read_tested(&4) }, 4);
+    let s = SomeStruct::safe_new_tested(2);
+    assert_eq!(s.safe_plus_tested(3u8), 5);
+}
+
+enum Choice<T> {
+    First(T),
+    Second(T, T),
+    Nothing,
+}
+
+impl<T: Copy + std::ops::Add<Output = T>> Choice<T> {
+    #[unsafe_fn]
+    fn sum(c: &Choice<T>, zero: T) -> T {
+        match *c {
+            Self::First(a) => a,
+            Self::Second(a, b) => a + b,
+            Self::Nothing => zero,
+        }
+    }
+}
+
+#[test]
+fn self_in_match_pattern() {
+    unsafe {
+        assert_eq!(Choice::sum(&Choice::First(3), 0), 3);
+        assert_eq!(Choice::sum(&Choice::Second(3, 4), 0), 7);
+        assert_eq!(Choice::sum(&Choice::Nothing, 0), 0);
+        // The body is in an associated function
+        assert_eq!(Choice::__unsafe_fn_sum(&Choice::Second(1, 1), 0), 2);
+    }
+}
+
+#[unsafe_fn]
+#[expect(unused_unsafe)]
+fn expect_on_body(x: u32) -> u32 {
+    unsafe { x + 1 }
+}
+
+#[test]
+fn expect_attribute() {
+    assert_eq!(unsafe { expect_on_body(1) }, 2);
+}
+
+#[derive(Clone)]
+struct Boxed<U>(U);
+
+impl From<Boxed<u32>> for u64 {
+    fn from(b: Boxed<u32>) -> u64 {
+        b.0 as u64
+    }
+}
+
+impl<U: Clone> Boxed<U> {
+    #[unsafe_fn]
+    fn convert<T: From<Self>>(&self) -> T {
+        self.clone().into()
+    }
+
+    // `Self` is only in the bound of a generic parameter
+    #[unsafe_fn]
+    fn make<T: From<Self>>(v: Boxed<U>) -> T {
+        T::from(v)
+    }
+}
+
+#[test]
+fn self_in_generic_bound() {
+    unsafe {
+        assert_eq!(Boxed(3u32).convert::<u64>(), 3);
+        assert_eq!(Boxed::make::<u64>(Boxed(4u32)), 4);
+        // The body is in an associated function
+        assert_eq!(Boxed::__unsafe_fn_make::<u64>(Boxed(5u32)), 5);
+    }
+}
+
+#[unsafe_fn]
+fn fill<T: Copy, const N: usize, U: Into<T>>(x: U) -> [T; N] {
+    [x.into(); N]
+}
+
+#[test]
+fn type_and_const_generics() {
+    assert_eq!(unsafe { fill::<u32, 3, u8>(2) }, [2, 2, 2]);
+    let arr: [u64; 2] = unsafe { fill(1u8) };
+    assert_eq!(arr, [1, 1]);
+}
+
+trait Measure {
+    #[unsafe_fn]
+    fn measure(&self, scale: u32) -> u32;
+
+    #[unsafe_fn]
+    fn r#type(&self) -> u32;
+}
+
+// What another macro would generate, following the documented naming rule
+impl Measure for String {
+    unsafe fn measure(&self, scale: u32) -> u32 {
+        self.__unsafe_fn_measure(scale)
+    }
+    fn __unsafe_fn_measure(&self, scale: u32) -> u32 {
+        self.len() as u32 * scale
+    }
+
+    unsafe fn r#type(&self) -> u32 {
+        self.__unsafe_fn_type()
+    }
+    fn __unsafe_fn_type(&self) -> u32 {
+        1
+    }
+}
+
+impl Measure for u32 {
+    #[unsafe_fn]
+    fn measure(&self, scale: u32) -> u32 {
+        *self * scale
+    }
+
+    #[unsafe_fn]
+    fn r#type(&self) -> u32 {
+        2
+    }
+}
+
+#[test]
+fn placeholder_naming() {
+    unsafe {
+        assert_eq!(String::from("abc").measure(2), 6);
+        assert_eq!(String::new().r#type(), 1);
+        assert_eq!(4u32.measure(2), 8);
+        assert_eq!(4u32.r#type(), 2);
+    }
+}
+
+fn block_on<F: std::future::Future>(f: F) -> F::Output {
+    let mut f = std::pin::pin!(f);
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+    loop {
+        if let std::task::Poll::Ready(v) = f.as_mut().poll(&mut cx) {
+            return v;
+        }
+    }
+}
+
+struct Job {
+    data: Vec<u32>,
+}
+
+impl Job {
+    #[unsafe_fn]
+    async fn run(self) -> u32 {
+        let first = unsafe { std::ptr::read(self.data.as_ptr()) };
+        let rest = std::future::ready(self.data.len() as u32).await;
+        drop(self);
+        first + rest
+    }
+}
+
+#[unsafe_fn]
+async fn add_async(a: u32, b: u32) -> u32 {
+    let b = async { b }.await;
+    unsafe { std::ptr::read(&a) + b }
+}
+
+#[test]
+fn async_fn() {
+    let job = Job { data: vec![10, 20] };
+    assert_eq!(block_on(unsafe { job.run() }), 12);
+    assert_eq!(block_on(unsafe { add_async(1, 2) }), 3);
+}
+
+#[unsafe_fn]
+#[allow(clippy::unused_unit)]
+fn explicit_unit(x: &mut u32) -> () {
+    unsafe { *(x as *mut u32) += 1 }
+}
+
+#[unsafe_fn]
+#[rustfmt::skip]
+#[allow(clippy::unused_unit, clippy::needless_return)]
+fn explicit_unit_spaced(x: &mut u32) -> ( ) {
+    *x += 2;
+    return ( );
+}
+
+#[test]
+fn explicit_unit_return() {
+    let mut x = 0;
+    let () = unsafe { explicit_unit(&mut x) };
+    let () = unsafe { explicit_unit_spaced(&mut x) };
+    assert_eq!(x, 3);
+}
+
+trait Shape {
+    fn area(&self) -> u32;
+}
+
+impl Shape for u32 {
+    fn area(&self) -> u32 {
+        *self * *self
+    }
+}
+
+impl dyn Shape {
+    #[unsafe_fn]
+    fn double_area(&self) -> u32 {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        self.area() * 2 + y
+    }
+
+    #[unsafe_fn]
+    fn boxed_area(b: Box<Self>) -> u32 {
+        b.area()
+    }
+}
+
+#[test]
+fn impl_dyn_trait() {
+    let s: &dyn Shape = &3u32;
+    assert_eq!(unsafe { s.double_area() }, 18);
+    assert_eq!(unsafe { <dyn Shape>::boxed_area(Box::new(2u32)) }, 4);
+}
+
+#[unsafe_fn]
+fn as_bytes<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    // Pointer arithmetic with wrapping_add does not need unsafe
+    let start = ptr.wrapping_add(1);
+    unsafe { std::slice::from_raw_parts(start, len - 1) }
+}
+
+struct RawBytes {
+    ptr: *const u8,
+    len: usize,
+}
+
+impl RawBytes {
+    #[unsafe_fn]
+    fn as_slice<'a>(&self) -> &'a [u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+#[test]
+fn from_raw_parts() {
+    let data = [1u8, 2, 3];
+    let bytes = unsafe { as_bytes(data.as_ptr(), data.len()) };
+    assert_eq!(bytes, [2, 3]);
+    let raw = RawBytes {
+        ptr: data.as_ptr(),
+        len: 2,
+    };
+    assert_eq!(unsafe { raw.as_slice() }, [1, 2]);
+}
+
+struct Slot<T>(T);
+
+impl<T: Copy> Slot<T> {
+    // Neither `self` nor `Self` is used, but the generic of the impl is
+    #[unsafe_fn]
+    fn identity(x: &T) -> &T {
+        x
+    }
+
+    #[unsafe_fn]
+    fn read_all(ptrs: &[*const T]) -> Vec<T> {
+        ptrs.iter().map(|p| unsafe { **p }).collect()
+    }
+}
+
+#[test]
+fn impl_generic_without_self() {
+    assert_eq!(unsafe { *Slot::identity(&3) }, 3);
+    let (a, b) = (1u8, 2u8);
+    assert_eq!(unsafe { Slot::read_all(&[&a, &b]) }, [1, 2]);
+}
+
+#[unsafe_fn]
+fn countdown(n: u8) -> impl Iterator<Item = u8> {
+    let v: Box<dyn Iterator<Item = u8>> = if n == 0 {
+        Box::new(std::iter::empty())
+    } else {
+        Box::new(std::iter::once(n).chain(unsafe { countdown(n - 1) }))
+    };
+    v
+}
+
+#[test]
+fn recursive_impl_trait() {
+    assert_eq!(unsafe { countdown(3) }.collect::<Vec<_>>(), [3, 2, 1]);
+}
+
+struct RawStr<'a>(&'a [u8]);
+
+impl<'a> RawStr<'a> {
+    #[unsafe_fn]
+    fn from_raw(p: *const u8, len: usize) -> RawStr<'a> {
+        RawStr(unsafe { std::slice::from_raw_parts(p, len) })
+    }
+
+    #[unsafe_fn]
+    fn first_of(items: &[RawStr<'a>]) -> &'a [u8] {
+        let y: usize = unsafe { std::mem::zeroed() };
+        items[y].0
+    }
+}
+
+#[test]
+fn impl_lifetime_without_self() {
+    let data = *b"hello";
+    let s = unsafe { RawStr::from_raw(data.as_ptr(), 4) };
+    assert_eq!(s.0, b"hell");
+    assert_eq!(unsafe { RawStr::first_of(&[s]) }, b"hell");
+}
+
+// A trait that does not use #[unsafe_fn], as if it came from another crate
+trait RawPoll {
+    unsafe fn poll_raw(&mut self) -> u32;
+    unsafe fn peek_raw(ptr: *const u32) -> u32;
+}
+
+struct Poller(u32);
+
+impl RawPoll for Poller {
+    #[safe_body]
+    unsafe fn poll_raw(&mut self) -> u32 {
+        self.0 += 1;
+        unsafe { std::ptr::read(&self.0) }
+    }
+
+    #[safe_body]
+    unsafe fn peek_raw(ptr: *const u32) -> u32 {
+        unsafe { *ptr }
+    }
+}
+
+#[test]
+fn safe_body_in_foreign_trait() {
+    let mut p = Poller(1);
+    assert_eq!(unsafe { p.poll_raw() }, 2);
+    assert_eq!(unsafe { Poller::peek_raw(&p.0) }, 2);
+}
+
+struct Registers<T>(Vec<T>);
+
+#[unsafe_fn]
+impl<T: Copy> Registers<T> {
+    fn read(&self, i: usize) -> T {
+        *unsafe { self.0.get_unchecked(i) }
+    }
+
+    fn write(&mut self, i: usize, v: T) {
+        unsafe { *self.0.get_unchecked_mut(i) = v }
+    }
+
+    // Neither `self` nor `Self`, but uses the generic of the impl
+    fn first(v: &[T]) -> T {
+        unsafe { *v.get_unchecked(0) }
+    }
+
+    unsafe fn already_unsafe(&self) -> usize {
+        self.0.len()
+    }
+
+    #[unsafe_fn(require_unsafe)]
+    fn own_attribute(&self) -> T {
+        unsafe { *self.0.get_unchecked(1) }
+    }
+}
+
+struct Impl(u32);
+
+#[unsafe_fn]
+unsafe impl UnsafeMethods for Impl {
+    fn required(&self) -> u32 {
+        self.0
+    }
+
+    fn provided_static() -> u32 {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        y + 20
+    }
+}
+
+#[test]
+fn impl_block() {
+    let mut r = Registers(vec![1u8, 2, 3]);
+    unsafe {
+        r.write(0, 5);
+        assert_eq!(r.read(0), 5);
+        assert_eq!(Registers::first(&[7u8]), 7);
+        assert_eq!(r.already_unsafe(), 3);
+        assert_eq!(r.own_attribute(), 2);
+        assert_eq!(Impl(4).required(), 4);
+        assert_eq!(Impl(4).provided(), 5);
+        assert_eq!(<Impl as UnsafeMethods>::provided_static(), 20);
+    }
+}
+
+struct Ffi(u32);
+
+#[safe_body]
+impl Ffi {
+    unsafe fn get(&self) -> u32 {
+        unsafe { std::ptr::read(&self.0) }
+    }
+
+    unsafe fn from_ptr(p: *const u32) -> Self {
+        Ffi(unsafe { *p })
+    }
+
+    // Not unsafe: left untouched
+    fn safe(&self) -> u32 {
+        self.0
+    }
+}
+
+#[safe_body]
+impl RawPoll for Ffi {
+    unsafe fn poll_raw(&mut self) -> u32 {
+        self.0 += 1;
+        unsafe { self.get() }
+    }
+
+    unsafe fn peek_raw(ptr: *const u32) -> u32 {
+        unsafe { *ptr }
+    }
+}
+
+#[test]
+fn safe_body_impl_block() {
+    let mut f = unsafe { Ffi::from_ptr(&3) };
+    assert_eq!(unsafe { f.get() }, 3);
+    assert_eq!(unsafe { f.poll_raw() }, 4);
+    assert_eq!(unsafe { Ffi::peek_raw(&f.0) }, 4);
+    assert_eq!(f.safe(), 4);
+}
+
+#[unsafe_fn(require_unsafe)]
+mod primitives {
+    pub fn load(p: *const u32) -> u32 {
+        unsafe { *p }
+    }
+
+    pub unsafe fn already_unsafe(p: *const u32) -> u32 {
+        *p + 1
+    }
+
+    pub mod nested {
+        pub fn store(p: *mut u32, v: u32) {
+            unsafe { *p = v }
+        }
+    }
+}
+
+#[test]
+fn unsafe_fn_on_module() {
+    let mut x = 1;
+    unsafe {
+        primitives::nested::store(&mut x, 5);
+        assert_eq!(primitives::load(&x), 5);
+        assert_eq!(primitives::already_unsafe(&x), 6);
+    }
+}
+
+#[safe_body]
+mod ffi {
+    pub struct Handle(pub u32);
+
+    impl Handle {
+        pub unsafe fn raw(&self) -> u32 {
+            unsafe { std::ptr::read(&self.0) }
+        }
+    }
+
+    pub unsafe fn open(p: *const u32) -> Handle {
+        Handle(unsafe { *p })
+    }
+
+    pub fn safe() -> u32 {
+        1
+    }
+
+    pub mod nested {
+        pub unsafe fn close(h: super::Handle) -> u32 {
+            let y: u32 = unsafe { std::mem::zeroed() };
+            h.0 + y
+        }
+    }
+}
+
+#[test]
+fn safe_body_on_module() {
+    let h = unsafe { ffi::open(&4) };
+    assert_eq!(unsafe { h.raw() }, 4);
+    assert_eq!(unsafe { ffi::nested::close(h) }, 4);
+    assert_eq!(ffi::safe(), 1);
+}
+
+struct Prefix(String);
+
+impl Prefix {
+    #[unsafe_fn]
+    async fn apply(&self, s: &str) -> String {
+        let s = std::future::ready(s).await;
+        let extra: usize = unsafe { std::mem::zeroed() };
+        format!("{}{}{}", self.0, s, extra)
+    }
+
+    #[unsafe_fn]
+    async fn first_or<'a>(items: &'a [Self], default: &'a str) -> &'a str {
+        let first = unsafe { items.get_unchecked(..1) };
+        async { first.first().map_or(default, |p| p.0.as_str()) }.await
+    }
+}
+
+#[unsafe_fn]
+async fn sum_borrowed(values: &[u32], extra: &u32) -> u32 {
+    let extra = async { *extra }.await;
+    values.iter().sum::<u32>() + unsafe { std::ptr::read(&extra) }
+}
+
+#[test]
+fn async_fn_with_borrows() {
+    let p = Prefix("a".into());
+    let s = String::from("b");
+    assert_eq!(block_on(unsafe { p.apply(&s) }), "ab0");
+    assert_eq!(
+        block_on(unsafe { Prefix::first_or(std::slice::from_ref(&p), "z") }),
+        "a"
+    );
+    assert_eq!(block_on(unsafe { sum_borrowed(&[1, 2], &3) }), 6);
+}
+
+#[unsafe_fn]
+fn read_array<const N: usize>(p: *const [u8; N]) -> [u8; N] {
+    unsafe { *p }
+}
+
+impl SomeStruct {
+    // The const parameter is declared before the type parameter
+    #[unsafe_fn]
+    fn pick_nth<const N: usize, T: Copy>(&self, p: *const [T; N]) -> T {
+        let arr = unsafe { *p };
+        arr[self.i as usize % N]
+    }
+}
+
+#[test]
+fn const_generic_parameters() {
+    assert_eq!(unsafe { read_array(&[1, 2, 3]) }, [1, 2, 3]);
+    let s = SomeStruct {
+        i: 4,
+        ..SomeStruct::default()
+    };
+    assert_eq!(unsafe { s.pick_nth(&[10u8, 11, 12]) }, 11);
+    assert_eq!(unsafe { s.pick_nth::<2, u16>(&[10, 11]) }, 10);
+}
+
+impl SomeStruct {
+    #[unsafe_fn]
+    fn with_cb<T: Default>(&self, f: impl FnOnce(*mut T)) -> T {
+        let mut value = T::default();
+        f(&mut value);
+        let y: u32 = unsafe { std::mem::zeroed() };
+        let _ = self.i + y;
+        value
+    }
+
+    #[unsafe_fn]
+    fn with_cb_assoc<T: Default>(f: impl FnOnce(*mut T), _s: Option<&Self>) -> T {
+        let mut value = T::default();
+        f(&mut value);
+        value
+    }
+}
+
+#[unsafe_fn]
+fn with_cb<T: Default>(f: impl FnOnce(*mut T)) -> T {
+    let mut value = T::default();
+    f(&mut value);
+    value
+}
+
+#[test]
+fn impl_trait_argument() {
+    let s = SomeStruct::default();
+    let set = |p: *mut u32| unsafe { *p = 7 };
+    assert_eq!(unsafe { s.with_cb(set) }, 7);
+    assert_eq!(unsafe { s.with_cb::<u32>(set) }, 7);
+    assert_eq!(unsafe { SomeStruct::with_cb_assoc(set, None) }, 7);
+    assert_eq!(unsafe { with_cb(set) }, 7);
+}
+
+#[unsafe_fn]
+fn iter_raw(p: *const u8, n: usize) -> impl Iterator<Item = u8> {
+    (0..n).map(move |i| unsafe { *p.add(i) })
+}
+
+impl SomeStruct {
+    // Only the lifetime of `data` is captured, not the one of `self`
+    #[unsafe_fn]
+    fn offsets<'a>(&self, data: &'a [u32]) -> impl Iterator<Item = u32> + 'a {
+        let i = self.i;
+        data.iter().map(move |x| unsafe { x.unchecked_add(i) })
+    }
+
+    #[unsafe_fn]
+    fn repeat_item<T: Clone>(&self, t: T) -> impl Iterator<Item = T> {
+        std::iter::repeat_n(t, self.i as usize)
+    }
+
+    #[unsafe_fn]
+    fn owned_range(s: &Self) -> impl Iterator<Item = u32> {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        y..s.i
+    }
+}
+
+#[test]
+fn return_position_impl_trait() {
+    let data = [1u8, 2, 3];
+    let v: Vec<_> = unsafe { iter_raw(data.as_ptr(), 3) }.collect();
+    assert_eq!(v, [1, 2, 3]);
+
+    let s = SomeStruct {
+        i: 2,
+        s: String::new(),
+    };
+    let values = [1, 2];
+    let offsets = unsafe { s.offsets(&values) };
+    let repeated = unsafe { s.repeat_item("x") };
+    let range = unsafe { SomeStruct::owned_range(&s) };
+    // The iterators do not borrow `s`
+    drop(s);
+    assert_eq!(offsets.collect::<Vec<_>>(), [3, 4]);
+    assert_eq!(repeated.collect::<Vec<_>>(), ["x", "x"]);
+    assert_eq!(range.collect::<Vec<_>>(), [0, 1]);
+}
+
+trait Source {
+    #[unsafe_fn]
+    fn values(&self) -> impl Iterator<Item = u8>;
+
+    #[unsafe_fn]
+    fn fetch(&self) -> impl std::future::Future<Output = u8>;
+
+    #[unsafe_fn]
+    fn filled<T: Clone>(t: T, n: usize) -> impl Iterator<Item = T>;
+}
+
+impl Source for Vec<u8> {
+    #[unsafe_fn]
+    fn values(&self) -> impl Iterator<Item = u8> {
+        let y: u8 = unsafe { std::mem::zeroed() };
+        self.clone().into_iter().map(move |x| x + y)
+    }
+
+    #[unsafe_fn]
+    fn fetch(&self) -> impl std::future::Future<Output = u8> {
+        let first = unsafe { *self.get_unchecked(0) };
+        async move { first }
+    }
+
+    // Without `self` nor `Self`, the body stays in place and the placeholder is not
+    // overridden
+    #[unsafe_fn]
+    fn filled<T: Clone>(t: T, n: usize) -> impl Iterator<Item = T> {
+        let y: usize = unsafe { std::mem::zeroed() };
+        std::iter::repeat_n(t, n + y)
+    }
+}
+
+#[test]
+fn impl_trait_in_trait() {
+    let v = vec![1u8, 2];
+    assert_eq!(unsafe { v.values() }.collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(block_on(unsafe { v.fetch() }), 1);
+    let filled = unsafe { <Vec<u8> as Source>::filled('x', 2) };
+    assert_eq!(filled.collect::<String>(), "xx");
+}
+
+struct Intrusive {
+    value: u32,
+    _pinned: std::marker::PhantomPinned,
+}
+
+impl Intrusive {
+    #[unsafe_fn]
+    fn bump(self: std::pin::Pin<&mut Self>) -> u32 {
+        let this = unsafe { self.get_unchecked_mut() };
+        this.value += 1;
+        this.value
+    }
+
+    #[unsafe_fn]
+    fn peek(self: std::pin::Pin<&Self>) -> u32 {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        self.value + y
+    }
+
+    #[unsafe_fn]
+    fn shared(self: std::rc::Rc<Self>) -> usize {
+        let y: usize = unsafe { std::mem::zeroed() };
+        std::rc::Rc::strong_count(&self) + y
+    }
+
+    #[safe_body]
+    unsafe fn reset(self: std::pin::Pin<&mut Self>) {
+        unsafe { self.get_unchecked_mut().value = 0 }
+    }
+}
+
+trait Task {
+    #[unsafe_fn]
+    fn run(self: std::sync::Arc<Self>) -> u32;
+}
+
+impl Task for Intrusive {
+    #[unsafe_fn]
+    fn run(self: std::sync::Arc<Self>) -> u32 {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        self.value + y
+    }
+}
+
+#[test]
+fn arbitrary_self_types() {
+    let mut i = Box::pin(Intrusive {
+        value: 1,
+        _pinned: std::marker::PhantomPinned,
+    });
+    unsafe {
+        assert_eq!(i.as_mut().bump(), 2);
+        assert_eq!(i.as_ref().peek(), 2);
+        i.as_mut().reset();
+        assert_eq!(i.as_ref().peek(), 0);
+    }
+    let rc = std::rc::Rc::new(Intrusive {
+        value: 3,
+        _pinned: std::marker::PhantomPinned,
+    });
+    let _other = rc.clone();
+    assert_eq!(unsafe { rc.shared() }, 2);
+    let arc = std::sync::Arc::new(Intrusive {
+        value: 4,
+        _pinned: std::marker::PhantomPinned,
+    });
+    assert_eq!(unsafe { arc.run() }, 4);
+}
+
+#[repr(C)]
+struct Ctx {
+    value: u32,
+}
+
+#[unsafe_fn]
+type Callback = extern "C" fn(*mut Ctx);
+
+#[unsafe_fn]
+pub type VariadicCallback = extern "C" fn(u32, ...) -> u32;
+
+#[unsafe_fn]
+extern "C" fn increment(ctx: *mut Ctx) {
+    unsafe { (*ctx).value += 1 }
+}
+
+#[test]
+fn function_pointer_alias() {
+    let cb: Callback = increment;
+    let callbacks: [Callback; 2] = [cb, increment];
+    let mut ctx = Ctx { value: 0 };
+    for cb in callbacks {
+        unsafe { cb(&mut ctx) };
+    }
+    assert_eq!(ctx.value, 2);
+    let _: Option<VariadicCallback> = None;
+}
+
+#[unsafe_fn]
+extern "C" {
+    pub safe fn abs(x: i32) -> i32;
+    pub unsafe fn strlen(s: *const std::os::raw::c_char) -> usize;
+    fn labs(x: std::os::raw::c_long) -> std::os::raw::c_long;
+}
+
+#[test]
+fn unsafe_extern_block() {
+    assert_eq!(abs(-3), 3);
+    assert_eq!(unsafe { strlen(b"abc\0".as_ptr().cast()) }, 3);
+    assert_eq!(unsafe { labs(-4) }, 4);
+}
+
+trait Viewable {
+    type View<'a>
+    where
+        Self: 'a;
+
+    #[unsafe_fn]
+    fn view<'a>(&'a self) -> Self::View<'a>;
+
+    // Trailing comma in the where clause
+    #[unsafe_fn]
+    #[rustfmt::skip]
+    fn view_at<'a>(&'a self, i: usize) -> Option<Self::View<'a>>
+    where
+        Self: 'a,;
+}
+
+impl Viewable for Vec<u8> {
+    type View<'a> = &'a [u8];
+
+    #[unsafe_fn]
+    fn view<'a>(&'a self) -> Self::View<'a> {
+        unsafe { self.get_unchecked(..) }
+    }
+
+    #[unsafe_fn]
+    fn view_at<'a>(&'a self, i: usize) -> Option<Self::View<'a>>
+    where
+        Self: 'a,
+    {
+        let y: usize = unsafe { std::mem::zeroed() };
+        self.get(i + y..)
+    }
+}
+
+#[test]
+fn generic_associated_types() {
+    let v = vec![1u8, 2, 3];
+    assert_eq!(unsafe { v.view() }, [1, 2, 3]);
+    assert_eq!(unsafe { v.view_at(1) }, Some(&[2u8, 3][..]));
+}
+
+trait Frob {
+    fn value(&self) -> u32;
+
+    #[safe_body]
+    unsafe fn frob(&self) -> u32 {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        self.value() + y
+    }
+
+    #[safe_body]
+    unsafe fn frob_static(p: *const u32) -> u32 {
+        unsafe { *p }
+    }
+
+    // Nothing to do without a body
+    #[safe_body]
+    unsafe fn frob_required(&self) -> u32;
+}
+
+impl Frob for u32 {
+    fn value(&self) -> u32 {
+        *self
+    }
+
+    unsafe fn frob_required(&self) -> u32 {
+        *self + 1
+    }
+}
+
+#[test]
+fn safe_body_trait_default() {
+    unsafe {
+        assert_eq!(3u32.frob(), 3);
+        assert_eq!(u32::frob_static(&4), 4);
+        assert_eq!(3u32.frob_required(), 4);
+    }
+}
+
+#[unsafe_fn(all_methods)]
+#[allow(clippy::missing_safety_doc)]
+trait LowLevel {
+    fn base(&self) -> u32;
+
+    unsafe fn offset(&self, p: *const u32) -> u32 {
+        unsafe { self.base() + *p }
+    }
+
+    fn doubled(&self) -> u32 {
+        unsafe { self.base() * 2 }
+    }
+}
+
+unsafe impl LowLevel for u32 {
+    #[unsafe_fn]
+    fn base(&self) -> u32 {
+        *self
+    }
+}
+
+#[test]
+fn all_methods() {
+    unsafe {
+        assert_eq!(3u32.base(), 3);
+        assert_eq!(3u32.offset(&2), 5);
+        assert_eq!(3u32.doubled(), 6);
+    }
+}
+
+#[unsafe_fn(unsafe_impl)]
+impl Marker for u32 {}
+
+#[unsafe_fn(unsafe_impl)]
+impl<T: Marker> Marker for Vec<T> {}
+
+trait ProvidedOnly {
+    fn provided_only(&self) -> u32 {
+        1
+    }
+}
+
+// Without `unsafe_impl`, an empty implementation of a safe trait stays safe
+#[unsafe_fn]
+impl ProvidedOnly for u32 {}
+
+fn assert_marker<T: Marker>() {}
+
+/// An unsafe trait with a doc comment
+///
+/// # Safety
+/// Only implemented for plain integers
+#[unsafe_fn]
+trait DocumentedMarker {}
+
+unsafe impl DocumentedMarker for u32 {}
+
+fn assert_documented_marker<T: DocumentedMarker>() {}
+
+#[test]
+fn unsafe_impl_marker() {
+    assert_marker::<u32>();
+    assert_marker::<Vec<u32>>();
+    assert_marker::<SomeStruct>();
+    assert_documented_marker::<u32>();
+    assert_eq!(3u32.provided_only(), 1);
+}
+
+unsafe_fn::unsafe_fn_items! {
+    fn items_read(ptr: *const u32) -> u32 {
+        unsafe { *ptr }
+    }
+
+    #[inline]
+    #[allow(clippy::missing_safety_doc)]
+    pub fn items_double(x: u32) -> u32 {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        x * 2 + y
+    }
+}
+
+macro_rules! raw_getter {
+    ($name:ident, $field:ident) => {
+        unsafe_fn::unsafe_fn_items! {
+            fn $name(&self) -> u32 {
+                unsafe { std::ptr::read(&self.$field) }
+            }
+        }
+    };
+}
+
+struct Fields {
+    a: u32,
+}
+
+impl Fields {
+    raw_getter!(get_a, a);
+}
+
+#[test]
+fn unsafe_fn_items() {
+    unsafe {
+        assert_eq!(items_read(&3), 3);
+        assert_eq!(items_double(3), 6);
+        assert_eq!(Fields { a: 4 }.get_a(), 4);
+    }
+}
+
+unsafe_fn::safe_body_items! {
+    unsafe fn body_items_read(ptr: *const u32) -> u32 {
+        unsafe { *ptr }
+    }
+
+    impl Fields {
+        unsafe fn get_a_twice(&self) -> u32 {
+            let y: u32 = unsafe { std::mem::zeroed() };
+            self.a * 2 + y
+        }
+    }
+}
+
+#[test]
+fn safe_body_items() {
+    unsafe {
+        assert_eq!(body_items_read(&3), 3);
+        assert_eq!(Fields { a: 4 }.get_a_twice(), 8);
+    }
+}
+
+#[test]
+fn unsafe_closure() {
+    let offset = 2;
+    let read = unsafe_fn::unsafe_closure!(move |p: *const u32, extra: u32| {
+        let y: u32 = unsafe { std::mem::zeroed() };
+        unsafe { *p + offset + extra + y }
+    });
+    assert_eq!(unsafe { read.call(&1, 3) }, 6);
+
+    let mut count = 0;
+    let mut incr = unsafe_fn::unsafe_closure!(|p: *mut u32| {
+        count += 1;
+        unsafe { *p += 1 }
+    });
+    let mut x = 0;
+    unsafe {
+        incr.call_mut(&mut x);
+        incr.call_mut(&mut x);
+    }
+    assert_eq!(x, 2);
+    assert_eq!(count, 2);
+
+    let unit = unsafe_fn::unsafe_closure!(|| 5);
+    assert_eq!(unsafe { unit.call() }, 5);
+}
+
+macro_rules! local_unsafe_fns {
+    ($name:ident, $arg:ident) => {
+        #[unsafe_fn]
+        fn $name($arg: *const u32) -> u32 {
+            unsafe { *$arg }
+        }
+        #[unsafe_fn(sibling)]
+        fn from_macro(ptr: *const u32, (a, b): (u32, u32)) -> u32 {
+            unsafe { *ptr + a + b }
+        }
+    };
+    ($vis:vis fn $name:ident($arg:ident: $t:ty) -> $r:ty $body:block) => {
+        #[unsafe_fn(sibling)]
+        $vis fn $name($arg: $t) -> $r $body
+    };
+}
+
+#[test]
+fn local_functions() {
+    #[unsafe_fn]
+    fn local(x: u32) -> u32 {
+        x + 1
+    }
+    #[unsafe_fn(sibling)]
+    fn local_sibling<T: Into<u32>>(x: T, (a, _): (u32, u32)) -> u32 {
+        x.into() + a
+    }
+    #[safe_body]
+    unsafe fn local_safe_body(x: u32) -> u32 {
+        x * 2
+    }
+    fn outer(x: u32) -> u32 {
+        #[unsafe_fn]
+        fn inner(x: u32) -> u32 {
+            x + 10
+        }
+        unsafe { inner(x) }
+    }
+    local_unsafe_fns!(read_local, p);
+    local_unsafe_fns!(
+        fn fragments(p: *const u32) -> u32 {
+            unsafe { *p * 2 }
+        }
+    );
+    unsafe {
+        assert_eq!(local(1), 2);
+        assert_eq!(local_sibling(3u8, (4, 5)), 7);
+        assert_eq!(local_safe_body(4), 8);
+        assert_eq!(read_local(&5), 5);
+        assert_eq!(from_macro(&1, (2, 3)), 6);
+        assert_eq!(fragments(&4), 8);
+    }
+    assert_eq!(outer(1), 11);
+}
+
+#[unsafe_fn]
+fn scan<F>(p: *const u8, len: usize, f: F) -> usize
+where
+    F: for<'a> Fn(&'a u8) -> bool,
+{
+    let slice = unsafe { std::slice::from_raw_parts(p, len) };
+    slice.iter().filter(|x| f(x)).count()
+}
+
+#[unsafe_fn(sibling)]
+fn scan_sibling<F, const N: usize>(p: *const [u8; N], mut f: F)
+where
+    for<'a> F: FnMut(&'a u8),
+{
+    for x in unsafe { &*p } {
+        f(x);
+    }
+}
+
+#[unsafe_fn(sibling)]
+fn scan_impl<T: Copy>(
+    p: *const T,
+    f: impl for<'a> Fn(&'a T) -> bool,
+    g: for<'a> fn(&'a T) -> &'a T,
+) -> bool {
+    f(g(unsafe { &*p }))
+}
+
+struct Scanner(Vec<u8>);
+impl Scanner {
+    #[unsafe_fn]
+    fn scan<F: for<'a> Fn(&'a u8) -> bool>(&self, extra: *const u8, f: F) -> usize
+    where
+        for<'a> &'a F: Copy,
+    {
+        self.0.iter().filter(|x| f(x)).count() + usize::from(f(unsafe { &*extra }))
+    }
+
+    #[unsafe_fn]
+    fn callback(p: *const u8) -> Box<dyn for<'a> Fn(&'a u8) -> u8> {
+        let base = unsafe { *p };
+        Box::new(move |x| x + base)
+    }
+}
+
+trait Scan {
+    #[unsafe_fn]
+    fn scan_with<F>(&self, f: F) -> usize
+    where
+        F: for<'a> Fn(&'a u8) -> bool;
+}
+
+impl Scan for Scanner {
+    #[unsafe_fn]
+    fn scan_with<F>(&self, f: F) -> usize
+    where
+        F: for<'a> Fn(&'a u8) -> bool,
+    {
+        self.0.iter().filter(|x| f(x)).count()
+    }
+}
+
+#[test]
+fn higher_ranked_bounds() {
+    let data = [1u8, 2, 3, 4];
+    let scanner = Scanner(data.to_vec());
+    let mut sum = 0;
+    unsafe {
+        assert_eq!(scan(data.as_ptr(), data.len(), |x| *x > 2), 2);
+        scan_sibling(&data, |x| sum += x);
+        assert!(scan_impl(&3u8, |x| *x == 3, |x| x));
+        assert_eq!(scanner.scan(&4, |x| *x % 2 == 0), 3);
+        assert_eq!(Scanner::callback(&10)(&1), 11);
+        assert_eq!(scanner.scan_with(|x| *x < 3), 2);
+    }
+    assert_eq!(sum, 10);
+}
+
+#[unsafe_fn]
+extern "system" fn abi_system(p: *const u32) -> u32 {
+    unsafe { *p }
+}
+
+#[unsafe_fn(sibling)]
+extern "C-unwind" fn abi_c_unwind(p: *const u32) -> u32 {
+    if unsafe { *p } == 0 {
+        panic!("zero");
+    }
+    unsafe { *p }
+}
+
+#[unsafe_fn(sibling)]
+extern "system-unwind" fn abi_system_unwind<T: Copy>(p: *const T) -> T {
+    unsafe { *p }
+}
+
+#[cfg(all(windows, target_arch = "x86"))]
+#[unsafe_fn]
+extern "stdcall" fn abi_stdcall(p: *const u32) -> u32 {
+    unsafe { *p }
+}
+
+#[unsafe_fn]
+type UnwindCallback = extern "C-unwind" fn(*const u32) -> u32;
+
+struct Abi(u32);
+impl Abi {
+    #[unsafe_fn]
+    extern "C-unwind" fn method(&self, p: *const u32) -> u32 {
+        self.0 + unsafe { *p }
+    }
+
+    #[unsafe_fn]
+    extern "Rust" fn rust(p: *const u32) -> Self {
+        Self(unsafe { *p })
+    }
+}
+
+#[test]
+fn abis() {
+    let system: unsafe extern "system" fn(*const u32) -> u32 = abi_system;
+    let c_unwind: UnwindCallback = abi_c_unwind;
+    let system_unwind: unsafe extern "system-unwind" fn(*const u8) -> u8 = abi_system_unwind;
+    let rust: unsafe fn(*const u32) -> Abi = Abi::rust;
+    unsafe {
+        assert_eq!(system(&1), 1);
+        assert_eq!(c_unwind(&2), 2);
+        assert_eq!(system_unwind(&3), 3);
+        assert_eq!(Abi(4).method(&5), 9);
+        assert_eq!(rust(&6).0, 6);
+    }
+    #[cfg(all(windows, target_arch = "x86"))]
+    assert_eq!(unsafe { abi_stdcall(&7) }, 7);
+    // A panic unwinds through the "C-unwind" function
+    assert!(std::panic::catch_unwind(|| unsafe { c_unwind(&0) }).is_err());
+}
+
+#[unsafe_fn]
+#[unsafe(no_mangle)]
+extern "C" fn unsafe_fn_test_unsafe_no_mangle(p: *const u32) -> u32 {
+    unsafe { *p }
+}
+
+#[unsafe_fn(sibling)]
+#[unsafe(export_name = "unsafe_fn_test_exported")]
+#[inline(never)]
+#[allow(clippy::missing_safety_doc)]
+pub extern "C" fn unsafe_export_name(p: *const u32) -> u32 {
+    unsafe { *p + 1 }
+}
+
+// The `unsafe(...)` attributes can also be given to `outer` and `inner`
+#[unsafe_fn(sibling, outer(unsafe(export_name = "unsafe_fn_test_outer_exported")))]
+#[inline(never)]
+#[allow(clippy::missing_safety_doc)]
+pub extern "C" fn outer_export_name(p: *const u32) -> u32 {
+    unsafe { *p + 2 }
+}
+
+#[cfg(target_os = "linux")]
+#[unsafe_fn(sibling, inner(unsafe(link_section = ".unsafe_fn_ram")))]
+fn inner_link_section(p: *const u32) -> u32 {
+    unsafe { *p + 3 }
+}
+
+#[test]
+fn unsafe_attributes() {
+    extern "C" {
+        fn unsafe_fn_test_unsafe_no_mangle(p: *const u32) -> u32;
+        fn unsafe_fn_test_exported(p: *const u32) -> u32;
+        fn unsafe_fn_test_outer_exported(p: *const u32) -> u32;
+    }
+    unsafe {
+        assert_eq!(unsafe_fn_test_unsafe_no_mangle(&1), 1);
+        assert_eq!(unsafe_fn_test_exported(&1), 2);
+        assert_eq!(unsafe_export_name(&2), 3);
+        assert_eq!(unsafe_fn_test_outer_exported(&1), 3);
+        assert_eq!(outer_export_name(&2), 4);
+        #[cfg(target_os = "linux")]
+        assert_eq!(inner_link_section(&1), 4);
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[unsafe_fn]
+#[unsafe(naked)]
+extern "C" fn naked_answer() -> u32 {
+    core::arch::naked_asm!("mov eax, 42", "ret")
+}
+
+#[cfg(target_arch = "x86_64")]
+#[test]
+fn naked() {
+    let f: unsafe extern "C" fn() -> u32 = naked_answer;
+    assert_eq!(unsafe { f() }, 42);
+}
+
+#[unsafe_fn(
+    sibling,
+    outer(track_caller, must_use),
+    inner(track_caller, inline(never))
+)]
+fn caller_line(p: *const u32) -> u32 {
+    std::panic::Location::caller().line() + unsafe { *p }
+}
+
+struct Routed(u32);
+impl Routed {
+    #[unsafe_fn(
+        outer(track_caller),
+        inner(track_caller, allow(clippy::let_and_return))
+    )]
+    fn caller_line(&self) -> u32 {
+        let line = std::panic::Location::caller().line() + self.0;
+        line
+    }
+
+    // Without `inner`, the helper is the caller: the location is in the body
+    #[unsafe_fn(outer(track_caller))]
+    fn helper_line(&self) -> (u32, u32) {
+        (std::panic::Location::caller().line(), line!())
+    }
+}
+
+#[test]
+fn attribute_routing() {
+    let line = line!();
+    assert_eq!(unsafe { caller_line(&0) }, line + 1);
+    assert_eq!(unsafe { Routed(0).caller_line() }, line + 2);
+    let (caller, body) = unsafe { Routed(0).helper_line() };
+    assert_eq!(caller, body);
+}
+
+#[cfg(target_arch = "x86_64")]
+mod simd {
+    use std::arch::x86_64::*;
+    use unsafe_fn::unsafe_fn;
+
+    /// Add the 8 integers of `b` to `a`
+    #[unsafe_fn(sibling)]
+    #[target_feature(enable = "avx2")]
+    pub fn add8(a: *mut [i32; 8], b: &[i32; 8]) {
+        let va = unsafe { _mm256_loadu_si256(a.cast()) };
+        let vb = unsafe { _mm256_loadu_si256(b.as_ptr().cast()) };
+        unsafe { _mm256_storeu_si256(a.cast(), _mm256_add_epi32(va, vb)) }
+    }
+
+    pub struct Kernel(pub [i32; 8]);
+    impl Kernel {
+        #[unsafe_fn]
+        #[target_feature(enable = "avx2")]
+        pub fn sum(&self) -> i32 {
+            let v = unsafe { _mm256_loadu_si256(self.0.as_ptr().cast()) };
+            let hi = _mm256_extracti128_si256(v, 1);
+            let s = _mm_add_epi32(_mm256_castsi256_si128(v), hi);
+            let s = _mm_hadd_epi32(s, s);
+            _mm_cvtsi128_si32(_mm_hadd_epi32(s, s))
+        }
+    }
+
+    pub trait Max {
+        #[unsafe_fn]
+        fn max(&self) -> i32;
+    }
+
+    impl Max for Kernel {
+        #[unsafe_fn]
+        #[target_feature(enable = "avx2")]
+        fn max(&self) -> i32 {
+            let v = unsafe { _mm256_loadu_si256(self.0.as_ptr().cast()) };
+            let m = _mm_max_epi32(_mm256_castsi256_si128(v), _mm256_extracti128_si256(v, 1));
+            let m = _mm_max_epi32(m, _mm_shuffle_epi32(m, 0b01_00_11_10));
+            _mm_cvtsi128_si32(_mm_max_epi32(m, _mm_shuffle_epi32(m, 0b10_11_00_01)))
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[test]
+fn target_feature() {
+    if !is_x86_feature_detected!("avx2") {
+        return;
+    }
+    let mut a = [1; 8];
+    unsafe {
+        simd::add8(&mut a, &[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(a, [2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(simd::Kernel(a).sum(), 44);
+        assert_eq!(simd::Max::max(&simd::Kernel(a)), 9);
+    }
+}
+
+#[unsafe_fn(sibling)]
+#[track_caller]
+fn tracked_free(p: *const u32) -> &'static std::panic::Location<'static> {
+    let _ = unsafe { *p };
+    std::panic::Location::caller()
+}
+
+struct Tracked;
+impl Tracked {
+    #[unsafe_fn]
+    #[track_caller]
+    fn tracked(&self, p: *const u32) -> u32 {
+        if unsafe { *p } == 0 {
+            panic!("zero");
+        }
+        std::panic::Location::caller().line()
+    }
+}
+
+#[test]
+fn track_caller() {
+    let line = line!();
+    assert_eq!(unsafe { tracked_free(&0) }.line(), line + 1);
+    assert_eq!(unsafe { tracked_free(&0) }.file(), file!());
+    assert_eq!(unsafe { Tracked.tracked(&1) }, line + 3);
+}
+
+#[repr(C)]
+pub struct Exported(u32);
+#[allow(clippy::missing_safety_doc)]
+impl Exported {
+    #[unsafe_fn(in_place)]
+    #[unsafe(no_mangle)]
+    pub extern "C" fn unsafe_fn_test_exported_method(&self, p: *const u32) -> u32 {
+        self.0 + unsafe { *p }
+    }
+
+    #[unsafe_fn(in_place)]
+    #[unsafe(export_name = "unsafe_fn_test_exported_new")]
+    pub extern "C" fn new(p: *const u32) -> Self {
+        Self(unsafe { *p })
+    }
+}
+
+#[test]
+fn in_place() {
+    extern "C" {
+        fn unsafe_fn_test_exported_method(this: &Exported, p: *const u32) -> u32;
+        fn unsafe_fn_test_exported_new(p: *const u32) -> Exported;
+    }
+    unsafe {
+        let e = unsafe_fn_test_exported_new(&4);
+        assert_eq!(unsafe_fn_test_exported_method(&e, &1), 5);
+        assert_eq!(Exported::new(&2).unsafe_fn_test_exported_method(&3), 5);
+    }
+}
+
+#[cfg(target_os = "linux")]
+struct Ram(u32);
+#[cfg(target_os = "linux")]
+impl Ram {
+    #[unsafe_fn]
+    #[link_section = ".unsafe_fn_ram"]
+    fn ram(&self, p: *const u32) -> u32 {
+        self.0 + unsafe { *p }
+    }
+
+    #[unsafe_fn]
+    #[unsafe(link_section = ".unsafe_fn_ram")]
+    fn unsafe_ram(&self, p: *const u32) -> u32 {
+        self.0 * unsafe { *p }
+    }
+}
+
+#[cfg(all(target_arch = "arm", target_feature = "v5te"))]
+#[unsafe_fn(sibling)]
+#[instruction_set(arm::t32)]
+fn thumb(p: *const u32) -> u32 {
+    unsafe { *p }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn link_section() {
+    assert_eq!(unsafe { Ram(1).ram(&2) }, 3);
+    assert_eq!(unsafe { Ram(3).unsafe_ram(&2) }, 6);
+    #[cfg(all(target_arch = "arm", target_feature = "v5te"))]
+    assert_eq!(unsafe { thumb(&2) }, 2);
+}
+
+struct Gated(u32);
+impl Gated {
+    #[unsafe_fn]
+    fn method(
+        &self,
+        #[cfg(not(test))] missing: u32,
+        #[cfg(test)] present: *const u32,
+        #[cfg(not(test))] (a, b): (u32, u32),
+        #[cfg(test)]
+        #[allow(unused_mut)]
+        (mut c, d): (u32, u32),
+    ) -> u32 {
+        self.0 + unsafe { *present } + c + d
+    }
+}
+
+#[unsafe_fn(sibling, debug_safe_test)]
+fn gated(#[cfg(not(test))] missing: u32, #[cfg(test)] present: u32, (x, _): (u32, u32)) -> u32 {
+    present + x
+}
+
+#[test]
+fn cfg_parameters() {
+    unsafe {
+        assert_eq!(Gated(1).method(&2, (3, 4)), 10);
+        assert_eq!(gated(1, (2, 3)), 3);
+    }
+}
+
+#[deny(deprecated)]
+mod deprecation {
+    use unsafe_fn::unsafe_fn;
+
+    #[unsafe_fn(debug_safe_test)]
+    #[deprecated(note = "use something else")]
+    pub fn old(p: *const u32) -> u32 {
+        unsafe { *p }
+    }
+
+    pub struct Old(pub u32);
+    impl Old {
+        #[unsafe_fn(debug_safe_test)]
+        #[deprecated]
+        pub fn get(&self, p: *const u32) -> u32 {
+            self.0 + unsafe { *p }
+        }
+    }
+}
+
+#[test]
+#[allow(deprecated)]
+fn deprecated() {
+    assert_eq!(unsafe { deprecation::old(&1) }, 1);
+    assert_eq!(deprecation::safe_old(&1), 1);
+    assert_eq!(deprecation::Old(1).safe_get(&1), 2);
+}
+
+struct Expecting(u32);
+impl Expecting {
+    #[unsafe_fn]
+    #[expect(clippy::too_many_arguments)]
+    #[allow(clippy::many_single_char_names)]
+    fn many(&self, a: u8, b: u8, c: u8, d: u8, e: u8, f: u8, g: *const u8) -> u32 {
+        let x = [a, b, c, d, e, f, unsafe { *g }];
+        self.0 + x.iter().map(|x| u32::from(*x)).sum::<u32>()
+    }
+
+    #[unsafe_fn]
+    #[expect(unused_variables)]
+    fn unused(&self, p: *const u32) -> u32 {
+        let unused = unsafe { *p };
+        self.0
+    }
+}
+
+#[unsafe_fn(sibling)]
+#[cfg_attr(test, expect(unused_variables, reason = "testing cfg_attr"), must_use)]
+fn unused_cfg(p: *const u32) -> u32 {
+    let unused = unsafe { *p };
+    0
+}
+
+#[test]
+fn lint_attributes() {
+    unsafe {
+        assert_eq!(Expecting(1).many(1, 1, 1, 1, 1, 1, &1), 8);
+        assert_eq!(Expecting(1).unused(&2), 1);
+        assert_eq!(unused_cfg(&2), 0);
+    }
+}
+
+#[cfg(not(test))]
+struct NotInTests;
+
+// `#[cfg]` on the items within a trait, impl block or module is only evaluated after
+// `#[unsafe_fn]`, so the generated functions need it too.
+#[unsafe_fn(methods)]
+#[allow(clippy::missing_safety_doc)]
+trait Net {
+    #[cfg(not(test))]
+    fn net(&self) -> NotInTests;
+
+    fn always(&self) -> u32;
+}
+
+struct Connection;
+#[unsafe_fn]
+unsafe impl Net for Connection {
+    #[cfg(not(test))]
+    fn net(&self) -> NotInTests {
+        NotInTests
+    }
+
+    fn always(&self) -> u32 {
+        1
+    }
+}
+
+#[unsafe_fn(debug_safe_test)]
+impl Connection {
+    #[cfg(not(test))]
+    fn inherent(&self) -> NotInTests {
+        NotInTests
+    }
+}
+
+#[unsafe_fn(sibling)]
+mod net {
+    #[cfg(not(test))]
+    fn removed() -> super::NotInTests {
+        super::NotInTests
+    }
+}
+
+#[test]
+fn cfg_removes_everything() {
+    assert_eq!(unsafe { Connection.always() }, 1);
+}
+
+#[cfg_attr(test, unsafe_fn::unsafe_fn, inline, must_use)]
+fn strict(p: *const u32) -> u32 {
+    unsafe { *p }
+}
+
+#[cfg_attr(not(test), unsafe_fn::unsafe_fn)]
+fn relaxed(x: u32) -> u32 {
+    x + 1
+}
+
+struct Conditional(u32);
+#[unsafe_fn]
+impl Conditional {
+    #[cfg_attr(test, unsafe_fn(require_unsafe), allow(clippy::let_and_return))]
+    fn own(&self, p: *const u32) -> u32 {
+        let x = self.0 + unsafe { *p };
+        x
+    }
+
+    fn block_method(&self) -> u32 {
+        self.0
+    }
+}
+
+#[unsafe_fn]
+mod conditional {
+    #[cfg_attr(test, unsafe_fn::safe_body)]
+    pub unsafe fn safe_body(x: u32) -> u32 {
+        x * 2
+    }
+}
+
+#[test]
+fn cfg_attr_unsafe_fn() {
+    unsafe {
+        assert_eq!(strict(&1), 1);
+        assert_eq!(Conditional(1).own(&2), 3);
+        assert_eq!(Conditional(1).block_method(), 1);
+        assert_eq!(conditional::safe_body(2), 4);
+    }
+    assert_eq!(relaxed(1), 2);
+}
+
+#[unsafe_fn]
+fn unsafe_in_brackets(p: *const u32, i: *const usize) -> [u32; 2] {
+    let a = [unsafe { *p }, 1];
+    [a[unsafe { *i }], vec![unsafe { *p }][0]]
+}
+
+#[test]
+fn unsafe_blocks_in_brackets() {
+    assert_eq!(unsafe { unsafe_in_brackets(&5, &1) }, [1, 5]);
+}
@@ -0,0 +1,11 @@
+use unsafe_fn::unsafe_fn;
+
+#[unsafe_fn(sibling, inner(deny(unused_variables)))]
+fn unused(x: u32) {
+    let y = x;
+}
+
+#[unsafe_fn(inner(#))]
+fn invalid() {}
+
+fn main() {}
@@ -0,0 +1,24 @@
+use unsafe_fn::unsafe_fn;
+
+struct S(u32);
+
+impl S {
+    #[unsafe_fn]
+    fn len(&self) -> usize {
+        let s: String = self.0;
+        s.len()
+    }
+}
+
+#[unsafe_fn]
+fn generic_len<T: Default>(x: u32) -> usize {
+    let s: String = x;
+    s.len() + std::mem::size_of::<T>()
+}
+
+fn main() {
+    unsafe {
+        S(1).len();
+        generic_len::<u8>(1);
+    }
+}
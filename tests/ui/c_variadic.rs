@@ -0,0 +1,13 @@
+use unsafe_fn::{safe_body, unsafe_fn};
+
+#[unsafe_fn]
+extern "C" fn sum(count: u32, args: ...) -> u32 {
+    count
+}
+
+#[safe_body]
+unsafe extern "C" fn sum2(count: u32, _: ...) -> u32 {
+    count
+}
+
+fn main() {}
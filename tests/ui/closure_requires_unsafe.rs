@@ -0,0 +1,12 @@
+use unsafe_fn::unsafe_fn;
+
+struct Slot<T>(T);
+
+impl<T: Copy> Slot<T> {
+    #[unsafe_fn]
+    fn read_all(ptrs: &[*const T]) -> Vec<T> {
+        ptrs.iter().map(|p| **p).collect()
+    }
+}
+
+fn main() {}
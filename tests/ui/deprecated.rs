@@ -0,0 +1,29 @@
+#![deny(deprecated)]
+use unsafe_fn::unsafe_fn;
+
+#[unsafe_fn]
+#[deprecated]
+fn in_place(p: *const u32) -> u32 {
+    unsafe { *p }
+}
+
+#[unsafe_fn(sibling)]
+#[deprecated(note = "use in_place")]
+fn sibling(p: *const u32) -> u32 {
+    unsafe { *p }
+}
+
+struct S(u32);
+impl S {
+    #[unsafe_fn]
+    #[deprecated]
+    fn method(&self, p: *const u32) -> u32 {
+        self.0 + unsafe { *p }
+    }
+}
+
+fn main() {
+    unsafe {
+        let _ = in_place(&1) + sibling(&1) + S(1).method(&1);
+    }
+}
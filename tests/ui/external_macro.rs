@@ -0,0 +1,31 @@
+use unsafe_fn::unsafe_fn;
+
+// Without `self` nor `Self`, the body stays in place, and the unsafe operations from
+// the macro of another crate are not reported
+#[unsafe_fn]
+pub fn read_in_place(p: *const u32) -> u32 {
+    external_macro::deref!(p)
+}
+
+// With a helper, they are reported
+#[unsafe_fn(sibling)]
+pub fn read_sibling(p: *const u32) -> u32 {
+    external_macro::deref!(p)
+}
+
+pub struct Ptr(*const u32);
+impl Ptr {
+    #[unsafe_fn]
+    pub fn read(&self) -> u32 {
+        external_macro::deref!(self.0)
+    }
+}
+
+#[unsafe_fn]
+mod raw {
+    pub fn read(p: *const u32) -> u32 {
+        external_macro::deref!(p)
+    }
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use unsafe_fn::unsafe_fn;
+
+#[unsafe_fn]
+fn as_bytes<'a>(ptr: *const u8, len: usize) -> &'a [u8] {
+    let start = ptr.wrapping_add(1);
+    std::slice::from_raw_parts(start, len - 1)
+}
+
+fn main() {}
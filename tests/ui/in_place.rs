@@ -0,0 +1,16 @@
+use unsafe_fn::unsafe_fn;
+
+#[unsafe_fn(in_place, sibling)]
+fn both(x: u32) -> u32 {
+    x
+}
+
+struct S;
+impl S {
+    #[unsafe_fn(in_place)]
+    fn get(&self, p: *const u32) -> u32 {
+        *p
+    }
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use unsafe_fn::unsafe_fn;
+
+#[unsafe_fn(methods)]
+fn not_a_trait() {}
+
+#[unsafe_fn(all_methods)]
+fn not_a_trait2() {}
+
+#[unsafe_fn(something_else)]
+fn unknown_option() {}
+
+fn main() {}
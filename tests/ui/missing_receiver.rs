@@ -0,0 +1,15 @@
+use unsafe_fn::unsafe_fn;
+
+trait Tr {
+    #[unsafe_fn]
+    fn get(&self) -> u32;
+}
+
+impl Tr for u32 {
+    #[unsafe_fn]
+    fn get() -> u32 {
+        42
+    }
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+#![deny(unused_must_use)]
+use unsafe_fn::unsafe_fn;
+
+#[unsafe_fn]
+#[must_use]
+fn in_place(p: *const u32) -> u32 {
+    unsafe { *p }
+}
+
+#[unsafe_fn(sibling)]
+#[must_use = "the value is read"]
+fn sibling(p: *const u32) -> u32 {
+    unsafe { *p }
+}
+
+struct S(u32);
+impl S {
+    #[unsafe_fn]
+    #[must_use]
+    fn method(&self, p: *const u32) -> u32 {
+        self.0 + unsafe { *p }
+    }
+}
+
+fn main() {
+    unsafe {
+        in_place(&1);
+        sibling(&1);
+        S(1).method(&1);
+    }
+}
@@ -0,0 +1,9 @@
+use unsafe_fn::unsafe_fn;
+
+#[unsafe_fn(sibling)]
+#[unsafe(naked)]
+extern "C" fn naked_answer() -> u32 {
+    core::arch::naked_asm!("mov eax, 42", "ret")
+}
+
+fn main() {}
@@ -0,0 +1,30 @@
+use unsafe_fn::unsafe_fn;
+
+struct S;
+
+impl S {
+    #[unsafe_fn]
+    const VALUE: u32 = 1;
+}
+
+trait Tr {
+    #[unsafe_fn]
+    const VALUE: u32;
+    #[unsafe_fn]
+    type Assoc;
+}
+
+impl Tr for S {
+    #[unsafe_fn]
+    const VALUE: u32 = 2;
+    #[unsafe_fn]
+    type Assoc = u32;
+}
+
+#[unsafe_fn]
+struct NotAFunction;
+
+#[unsafe_fn]
+type NotAFunctionPointer = u32;
+
+fn main() {}
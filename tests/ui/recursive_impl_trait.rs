@@ -0,0 +1,15 @@
+use unsafe_fn::unsafe_fn;
+
+struct Tree(Vec<u8>);
+
+impl Tree {
+    #[unsafe_fn]
+    fn expand(&self, depth: u32) -> impl Iterator<Item = u8> + '_ {
+        if depth == 0 {
+            return self.0.iter().copied();
+        }
+        unsafe { self.expand(depth - 1) }
+    }
+}
+
+fn main() {}
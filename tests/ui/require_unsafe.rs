@@ -0,0 +1,14 @@
+use unsafe_fn::unsafe_fn;
+
+#[unsafe_fn(require_unsafe)]
+fn noop() {}
+
+#[unsafe_fn(require_unsafe)]
+fn only_nested_unsafe() -> u32 {
+    fn nested() -> u32 {
+        unsafe { std::mem::zeroed() }
+    }
+    nested()
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+use unsafe_fn::safe_body;
+
+#[safe_body]
+mod ffi {
+    pub struct Handle(pub *const u32);
+
+    impl Handle {
+        pub unsafe fn get(&self) -> u32 {
+            *self.0
+        }
+    }
+
+    pub unsafe fn read(p: *const u32) -> u32 {
+        *p
+    }
+}
+
+fn main() {}
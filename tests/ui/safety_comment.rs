@@ -0,0 +1,18 @@
+use unsafe_fn::{safety, unsafe_fn};
+
+#[unsafe_fn(safety_comment)]
+fn unjustified(ptr: *const u32) -> u32 {
+    let x = unsafe {
+        safety!("the caller guarantees that ptr is valid");
+        *ptr
+    };
+    // SAFETY: comments are not visible to the macro
+    let y: u32 = unsafe { std::mem::zeroed() };
+    x + y + unsafe { *ptr } + vec![unsafe { *ptr }][0]
+}
+
+fn not_a_literal() {
+    safety!(the caller guarantees that ptr is valid);
+}
+
+fn main() {}
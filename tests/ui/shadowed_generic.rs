@@ -0,0 +1,19 @@
+use unsafe_fn::unsafe_fn;
+
+struct Foo<T>(T);
+
+// Shadowing the generic parameter of the impl is not allowed by Rust, the macro
+// must report the same error as for a regular function
+impl<T> Foo<T> {
+    #[unsafe_fn]
+    fn f<T>(x: T) -> T {
+        x
+    }
+
+    #[unsafe_fn]
+    fn g<T>(&self, x: T) -> T {
+        x
+    }
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use unsafe_fn::unsafe_fn;
+
+static mut COUNTER: u32 = 0;
+
+#[unsafe_fn]
+fn increment_counter() -> u32 {
+    COUNTER += 1;
+    1
+}
+
+fn main() {}
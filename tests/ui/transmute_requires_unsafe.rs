@@ -0,0 +1,16 @@
+use unsafe_fn::unsafe_fn;
+
+#[repr(transparent)]
+struct Meters(u32);
+
+#[repr(transparent)]
+struct Feet(u32);
+
+impl Meters {
+    #[unsafe_fn]
+    fn as_feet(&self) -> &Feet {
+        std::mem::transmute::<&Meters, &Feet>(self)
+    }
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+#![deny(unfulfilled_lint_expectations)]
+use unsafe_fn::unsafe_fn;
+
+struct S(u32);
+impl S {
+    #[unsafe_fn]
+    #[expect(unused_variables)]
+    fn get(&self, p: *const u32) -> u32 {
+        self.0 + unsafe { *p }
+    }
+}
+
+fn main() {
+    let _ = unsafe { S(1).get(&1) };
+}
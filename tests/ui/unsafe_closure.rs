@@ -0,0 +1,3 @@
+fn main() {
+    let untyped = unsafe_fn::unsafe_closure!(|p| p + 1);
+}
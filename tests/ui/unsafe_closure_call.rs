@@ -0,0 +1,5 @@
+fn main() {
+    let read = unsafe_fn::unsafe_closure!(|p: *const u8| unsafe { *p });
+    // Calling it requires unsafe
+    read.call(&1);
+}
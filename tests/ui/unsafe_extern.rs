@@ -0,0 +1,8 @@
+use unsafe_fn::unsafe_fn;
+
+#[unsafe_fn]
+unsafe extern "C" {
+    safe fn abs(x: i32) -> i32;
+}
+
+fn main() {}
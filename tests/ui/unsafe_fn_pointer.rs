@@ -0,0 +1,13 @@
+use unsafe_fn::unsafe_fn;
+
+#[unsafe_fn]
+type Callback = fn(u32);
+
+#[unsafe_fn]
+type AlreadyUnsafe = unsafe fn(u32);
+
+fn call(cb: Callback) {
+    cb(1);
+}
+
+fn main() {}
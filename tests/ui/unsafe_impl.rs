@@ -0,0 +1,19 @@
+use unsafe_fn::unsafe_fn;
+
+#[unsafe_fn]
+trait Marker {}
+
+#[unsafe_fn(unsafe_impl)]
+unsafe impl Marker for u32 {}
+
+struct Inherent;
+#[unsafe_fn(unsafe_impl)]
+impl Inherent {}
+
+#[unsafe_fn(unsafe_impl)]
+trait NotAnImpl {}
+
+#[unsafe_fn]
+unsafe trait AlreadyUnsafe {}
+
+fn main() {}